@@ -0,0 +1,279 @@
+use crate::{Deck, Entry, Owner, Parsed, SkippedRow, User};
+
+pub const BUNDLE_FILE: &str = "collections.bin";
+
+/// Bumped whenever the layout of `Bundle` changes, older bundles are rejected
+pub const BUNDLE_VERSION: u16 = 3;
+
+const MAGIC: &[u8; 4] = b"MTGB";
+
+/// A member's collection, decks and wantlist as read back from a bundle
+pub type Unbundled = (
+    User,
+    anyhow::Result<Parsed>,
+    anyhow::Result<Vec<Deck>>,
+    String,
+);
+
+/// Every member's collection snapshot in one file, written by `build.rs` for the web build
+///
+/// Laid out as `MAGIC`, `BUNDLE_VERSION` in little endian, then the deflated postcard encoding
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct Bundle {
+    /// Seconds since the unix epoch when the snapshot was taken
+    pub created: u64,
+    pub owners: Vec<Snapshot>,
+}
+
+/// One member's collection, decks and wantlist, or why they couldn't be fetched
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct Snapshot {
+    pub user: User,
+    pub contents: Result<Contents, String>,
+    pub decks: Result<Vec<Deck>, String>,
+    /// The contents of the member's file in `WANTS_DIR`, empty when they have none
+    pub wants: String,
+}
+
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+pub struct Contents {
+    pub cards: Vec<Card>,
+    pub skipped: Vec<SkippedRow>,
+}
+
+/// An `Entry` without its owner, `Entry` itself relies on self-describing formats
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+pub struct Card {
+    pub quantity: u32,
+    pub color_identity: String,
+    pub name: String,
+    pub ty: String,
+    pub set: String,
+    pub scryfall: String,
+    pub price: Option<f32>,
+}
+
+impl Bundle {
+    pub fn new(created: u64) -> Self {
+        Self {
+            created,
+            owners: Vec::new(),
+        }
+    }
+
+    pub fn push(
+        &mut self,
+        user: User,
+        fetched: Result<&Parsed, String>,
+        decks: Result<Vec<Deck>, String>,
+        wants: String,
+    ) {
+        let contents = fetched.map(|parsed| Contents {
+            cards: parsed
+                .entries
+                .iter()
+                .map(|entry| Card {
+                    quantity: entry.quantity,
+                    color_identity: entry.color_identity.into(),
+                    name: entry.name.clone(),
+                    ty: entry.ty.clone(),
+                    set: entry.set.clone(),
+                    scryfall: entry.scryfall.clone(),
+                    price: entry.price,
+                })
+                .collect(),
+            skipped: parsed.skipped.clone(),
+        });
+        self.owners.push(Snapshot {
+            user,
+            contents,
+            decks,
+            wants,
+        });
+    }
+
+    pub fn to_bytes(&self) -> anyhow::Result<Vec<u8>> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend(BUNDLE_VERSION.to_le_bytes());
+        bytes.extend(miniz_oxide::deflate::compress_to_vec(
+            &postcard::to_stdvec(self)?,
+            6,
+        ));
+        Ok(bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> anyhow::Result<Self> {
+        let Some((MAGIC, rest)) = bytes.split_first_chunk::<4>() else {
+            anyhow::bail!("Not a collection bundle");
+        };
+        let Some((version, rest)) = rest.split_first_chunk::<2>() else {
+            anyhow::bail!("Truncated collection bundle");
+        };
+        let version = u16::from_le_bytes(*version);
+        if version != BUNDLE_VERSION {
+            anyhow::bail!("Collection bundle version {version}, expected {BUNDLE_VERSION}");
+        }
+
+        let data = miniz_oxide::inflate::decompress_to_vec(rest)
+            .map_err(|e| anyhow::anyhow!("Corrupt collection bundle: {e}"))?;
+        Ok(postcard::from_bytes(&data)?)
+    }
+
+    /// Turns the snapshots back into entries, decks and wantlists, one of each per member
+    pub fn into_parsed(self) -> impl Iterator<Item = Unbundled> {
+        self.owners.into_iter().map(
+            |Snapshot {
+                 user,
+                 contents,
+                 decks,
+                 wants,
+             }| {
+                let parsed =
+                    contents
+                        .map_err(anyhow::Error::msg)
+                        .map(|Contents { cards, skipped }| {
+                            let rows = cards.into_iter().map(|card| -> anyhow::Result<Entry> {
+                                Ok(Entry {
+                                    owner: Owner::Member(user),
+                                    quantity: card.quantity,
+                                    color_identity: card
+                                        .color_identity
+                                        .parse()
+                                        .map_err(anyhow::Error::msg)?,
+                                    name: card.name,
+                                    ty: card.ty,
+                                    set: card.set,
+                                    scryfall: card.scryfall,
+                                    price: card.price,
+                                })
+                            });
+                            let mut parsed = Parsed::from_rows(rows);
+                            parsed.skipped.extend(skipped);
+                            parsed
+                        });
+                (user, parsed, decks.map_err(anyhow::Error::msg), wants)
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ColorIdent, DeckCard};
+
+    fn parsed() -> Parsed {
+        Parsed {
+            entries: vec![Entry {
+                owner: User::Strosel.into(),
+                quantity: 2,
+                color_identity: "UBG".parse().unwrap(),
+                name: "Sol Ring".into(),
+                ty: "Artifact".into(),
+                set: "c21".into(),
+                scryfall: "abc".into(),
+                price: Some(1.5),
+            }],
+            skipped: vec![SkippedRow {
+                row: 3,
+                reason: "bad quantity".into(),
+            }],
+        }
+    }
+
+    fn deck() -> Deck {
+        Deck {
+            id: 7,
+            name: "Artifacts".into(),
+            owner: User::Strosel,
+            cards: vec![DeckCard {
+                quantity: 1,
+                name: "Sol Ring".into(),
+                set: "c21".into(),
+                scryfall: "abc".into(),
+            }],
+        }
+    }
+
+    fn bytes() -> Vec<u8> {
+        let mut bundle = Bundle::new(1234);
+        bundle.push(
+            User::Strosel,
+            Ok(&parsed()),
+            Ok(vec![deck()]),
+            "Mind Stone".into(),
+        );
+        bundle.push(
+            User::VikinGG,
+            Err("private".into()),
+            Err("timed out".into()),
+            String::new(),
+        );
+        bundle.to_bytes().unwrap()
+    }
+
+    #[test]
+    fn snapshots_survive_write_and_read() {
+        let bundle = Bundle::from_bytes(&bytes()).unwrap();
+        assert_eq!(bundle.created, 1234);
+
+        let mut owners = bundle.into_parsed();
+        let (user, parsed, decks, wants) = owners.next().unwrap();
+        assert_eq!(user, User::Strosel);
+        let parsed = parsed.unwrap();
+        let entry = &parsed.entries[0];
+        assert_eq!(entry.owner, Owner::Member(User::Strosel));
+        assert_eq!(
+            (entry.quantity, entry.name.as_str(), entry.set.as_str()),
+            (2, "Sol Ring", "c21")
+        );
+        assert_eq!(entry.color_identity, "UBG".parse::<ColorIdent>().unwrap());
+        assert_eq!(
+            (entry.ty.as_str(), entry.scryfall.as_str(), entry.price),
+            ("Artifact", "abc", Some(1.5))
+        );
+        assert_eq!(parsed.skipped.len(), 1);
+        assert_eq!(parsed.skipped[0].row, 3);
+        assert_eq!(decks.unwrap(), [deck()]);
+        assert_eq!(wants, "Mind Stone");
+
+        let (user, parsed, decks, wants) = owners.next().unwrap();
+        assert_eq!(user, User::VikinGG);
+        assert_eq!(parsed.unwrap_err().to_string(), "private");
+        assert_eq!(decks.unwrap_err().to_string(), "timed out");
+        assert!(wants.is_empty());
+        assert!(owners.next().is_none());
+    }
+
+    #[test]
+    fn rejects_other_files() {
+        let mut bytes = bytes();
+        bytes[0] = b'X';
+        assert!(Bundle::from_bytes(&bytes).is_err());
+        assert!(Bundle::from_bytes(b"owner,name\n").is_err());
+        assert!(Bundle::from_bytes(b"").is_err());
+    }
+
+    #[test]
+    fn rejects_other_versions() {
+        let mut bytes = bytes();
+        bytes[4..6].copy_from_slice(&(BUNDLE_VERSION - 1).to_le_bytes());
+        let error = Bundle::from_bytes(&bytes).unwrap_err();
+        assert!(error.to_string().contains("version"), "{error}");
+
+        bytes[4..6].copy_from_slice(&(BUNDLE_VERSION + 1).to_le_bytes());
+        assert!(Bundle::from_bytes(&bytes).is_err());
+    }
+
+    #[test]
+    fn rejects_truncated_and_corrupt_bundles() {
+        let bytes = bytes();
+        assert!(Bundle::from_bytes(&bytes[..5]).is_err());
+        assert!(Bundle::from_bytes(&bytes[..bytes.len() / 2]).is_err());
+
+        let mut corrupt = bytes.clone();
+        corrupt.truncate(6);
+        corrupt.extend([0xFF; 16]);
+        assert!(Bundle::from_bytes(&corrupt).is_err());
+    }
+}
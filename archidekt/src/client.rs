@@ -0,0 +1,495 @@
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+use crate::deck::{deck_path, DeckPage, RawDeck};
+use crate::{parse_export, ArchidektError, Body, Deck, DeckList, Parsed, Response, User, BASE_URL};
+
+/// Limits how many requests a client and its clones have in flight at once
+#[derive(Debug)]
+struct Permits {
+    free: Mutex<usize>,
+    released: Condvar,
+}
+
+struct Permit<'a>(&'a Permits);
+
+impl Permits {
+    fn acquire(&self) -> Permit<'_> {
+        let mut free = self.free.lock().unwrap();
+        while *free == 0 {
+            free = self.released.wait(free).unwrap();
+        }
+        *free -= 1;
+        Permit(self)
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        *self.0.free.lock().unwrap() += 1;
+        self.0.released.notify_one();
+    }
+}
+
+pub struct ArchidektClientBuilder {
+    base_url: String,
+    timeout: Duration,
+    user_agent: String,
+    retries: u32,
+    backoff: Duration,
+    max_delay: Duration,
+    concurrency: usize,
+}
+
+impl Default for ArchidektClientBuilder {
+    fn default() -> Self {
+        Self {
+            base_url: BASE_URL.to_string(),
+            timeout: Duration::from_secs(30),
+            user_agent: concat!("archidekt/", env!("CARGO_PKG_VERSION")).to_string(),
+            retries: 3,
+            backoff: Duration::from_secs(1),
+            max_delay: Duration::from_secs(60),
+            concurrency: 2,
+        }
+    }
+}
+
+impl ArchidektClientBuilder {
+    /// Where the Archidekt API lives, e.g. a local mock server
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Timeout of a single attempt
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = user_agent.into();
+        self
+    }
+
+    /// How many times to retry after network errors, 429 or 5xx
+    pub fn retries(mut self, retries: u32) -> Self {
+        self.retries = retries;
+        self
+    }
+
+    /// Delay before the first retry, doubled for every following one
+    pub fn backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Longest wait before a retry, both for the backoff and the server's Retry-After
+    pub fn max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Maximum number of requests in flight at once
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    pub fn build(self) -> ArchidektClient {
+        ArchidektClient {
+            agent: ureq::AgentBuilder::new()
+                .timeout(self.timeout)
+                .user_agent(&self.user_agent)
+                .build(),
+            base_url: self.base_url,
+            retries: self.retries,
+            backoff: self.backoff,
+            max_delay: self.max_delay,
+            permits: Arc::new(Permits {
+                free: Mutex::new(self.concurrency),
+                released: Condvar::new(),
+            }),
+        }
+    }
+}
+
+/// Blocking Archidekt API client, clones share their connection pool and concurrency limit
+#[derive(Debug, Clone)]
+pub struct ArchidektClient {
+    agent: ureq::Agent,
+    base_url: String,
+    retries: u32,
+    backoff: Duration,
+    max_delay: Duration,
+    permits: Arc<Permits>,
+}
+
+impl Default for ArchidektClient {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ArchidektClient {
+    pub fn new() -> Self {
+        Self::builder().build()
+    }
+
+    pub fn builder() -> ArchidektClientBuilder {
+        Default::default()
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Sends a request, retrying with exponential backoff on network errors, 429 and 5xx
+    fn send<T: serde::de::DeserializeOwned>(
+        &self,
+        method: &str,
+        path: &str,
+        body: Option<&str>,
+    ) -> Result<T, ArchidektError> {
+        let url = format!("{}{path}", self.base_url);
+        let mut attempt = 0;
+        loop {
+            let result = {
+                let _permit = self.permits.acquire();
+                let req = self
+                    .agent
+                    .request(method, &url)
+                    .set("Accept", "application/json");
+                match body {
+                    Some(body) => req
+                        .set("Content-Type", "application/json")
+                        .send_string(body),
+                    None => req.call(),
+                }
+            };
+
+            let (error, retry_after) = match result {
+                Ok(resp) => return Ok(serde_json::from_reader(resp.into_reader())?),
+                Err(ureq::Error::Status(status, resp)) => (
+                    ArchidektError::from_status(status, resp.status_text()),
+                    resp.header("Retry-After")
+                        .and_then(|secs| secs.parse().ok())
+                        .map(Duration::from_secs),
+                ),
+                Err(ureq::Error::Transport(e)) => (ArchidektError::Network(e.to_string()), None),
+            };
+
+            let retryable = match &error {
+                ArchidektError::Network(_) => true,
+                ArchidektError::Status { status, .. } => *status == 429 || *status >= 500,
+                _ => false,
+            };
+            if !retryable || attempt >= self.retries {
+                return Err(error);
+            }
+
+            let delay = retry_after
+                .unwrap_or_else(|| {
+                    self.backoff
+                        .checked_mul(2u32.saturating_pow(attempt))
+                        .unwrap_or(Duration::MAX)
+                })
+                .min(self.max_delay);
+            log::warn!("{error}, retrying {url} in {delay:?}");
+            std::thread::sleep(delay);
+            attempt = attempt.saturating_add(1);
+        }
+    }
+
+    fn get<T: serde::de::DeserializeOwned>(&self, path: &str) -> Result<T, ArchidektError> {
+        self.send("GET", path, None)
+    }
+
+    fn post<T: serde::de::DeserializeOwned>(
+        &self,
+        path: &str,
+        body: &impl serde::Serialize,
+    ) -> Result<T, ArchidektError> {
+        self.send("POST", path, Some(&serde_json::to_string(body)?))
+    }
+
+    /// Gets a user's collection
+    pub fn collection(&self, owner: User) -> Result<Parsed, ArchidektError> {
+        let data: Response = self.post(
+            &format!("/api/collection/export/v2/{}/", owner.id()),
+            &Body::export(),
+        )?;
+
+        parse_export(owner, &data.content)
+    }
+
+    /// Gets a user's public decks with their cards
+    pub fn decks(&self, owner: User) -> Result<Vec<Deck>, ArchidektError> {
+        let mut ids = Vec::new();
+        let mut next = Some(format!(
+            "/api/decks/cards/?owner={owner}&ownerexact=true&pageSize=50"
+        ));
+        while let Some(path) = next {
+            let page: DeckPage = self.get(&path)?;
+            ids.extend(page.results.into_iter().map(|deck| deck.id));
+            // `next` is a full URL, possibly with another scheme than `base_url`
+            next = page
+                .next
+                .and_then(|url| url.find("/api/").map(|i| url[i..].to_string()));
+        }
+
+        ids.into_iter()
+            .map(|id| {
+                let deck: RawDeck = self.get(&deck_path(id))?;
+                Ok(deck.into_deck(owner))
+            })
+            .collect()
+    }
+
+    /// Gets any public deck with all its cards, e.g. to use as a wantlist
+    pub fn deck_list(&self, id: u32) -> Result<DeckList, ArchidektError> {
+        let deck: RawDeck = self.get(&deck_path(id))?;
+        Ok(deck.into_list())
+    }
+
+    /// Gets the decks of several users in parallel, within the client's concurrency limit
+    pub fn all_decks(&self, owners: &[User]) -> Vec<(User, Result<Vec<Deck>, ArchidektError>)> {
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = owners
+                .iter()
+                .map(|owner| (*owner, scope.spawn(|| self.decks(*owner))))
+                .collect();
+            handles
+                .into_iter()
+                .map(|(owner, handle)| {
+                    let fetched = handle.join().unwrap_or_else(|_| {
+                        Err(ArchidektError::Network(format!(
+                            "Fetching decks of {owner} panicked"
+                        )))
+                    });
+                    (owner, fetched)
+                })
+                .collect()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Instant;
+
+    use super::*;
+
+    /// A canned answer of the mock server
+    struct Reply {
+        status: u16,
+        headers: &'static str,
+        body: &'static str,
+    }
+
+    const OK: Reply = Reply {
+        status: 200,
+        headers: "",
+        body: "{}",
+    };
+
+    fn status(status: u16) -> Reply {
+        Reply {
+            status,
+            headers: "",
+            body: "",
+        }
+    }
+
+    /// Serves `reply(n)` to the n:th request, each on its own thread after `delay`
+    struct MockServer {
+        url: String,
+        requests: Arc<AtomicUsize>,
+        /// The most requests handled at once
+        peak: Arc<AtomicUsize>,
+    }
+
+    impl MockServer {
+        fn start(delay: Duration, reply: fn(usize) -> Reply) -> Self {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let url = format!("http://{}", listener.local_addr().unwrap());
+            let requests = Arc::new(AtomicUsize::new(0));
+            let peak = Arc::new(AtomicUsize::new(0));
+            let active = Arc::new(AtomicUsize::new(0));
+
+            let (counter, max) = (requests.clone(), peak.clone());
+            std::thread::spawn(move || {
+                for stream in listener.incoming().flatten() {
+                    let n = counter.fetch_add(1, Ordering::SeqCst);
+                    let (active, max) = (active.clone(), max.clone());
+                    std::thread::spawn(move || {
+                        let now = active.fetch_add(1, Ordering::SeqCst) + 1;
+                        max.fetch_max(now, Ordering::SeqCst);
+                        std::thread::sleep(delay);
+                        active.fetch_sub(1, Ordering::SeqCst);
+                        answer(stream, reply(n));
+                    });
+                }
+            });
+
+            Self {
+                url,
+                requests,
+                peak,
+            }
+        }
+
+        fn requests(&self) -> usize {
+            self.requests.load(Ordering::SeqCst)
+        }
+
+        fn client(&self) -> ArchidektClientBuilder {
+            ArchidektClient::builder()
+                .base_url(&self.url)
+                .backoff(Duration::from_millis(1))
+        }
+    }
+
+    fn answer(stream: TcpStream, reply: Reply) {
+        let mut reader = BufReader::new(stream);
+        let mut length = 0;
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line).unwrap_or(0) == 0 || line == "\r\n" {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                if name.eq_ignore_ascii_case("content-length") {
+                    length = value.trim().parse().unwrap_or(0);
+                }
+            }
+        }
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).ok();
+
+        let mut stream = reader.into_inner();
+        write!(
+            stream,
+            "HTTP/1.1 {} Mock\r\n{}Content-Type: application/json\r\nContent-Length: {}\r\n\
+             Connection: close\r\n\r\n{}",
+            reply.status,
+            reply.headers,
+            reply.body.len(),
+            reply.body
+        )
+        .ok();
+    }
+
+    fn fetch(client: &ArchidektClient) -> Result<serde_json::Value, ArchidektError> {
+        client.send("GET", "/api/", None)
+    }
+
+    #[test]
+    fn retries_server_errors() {
+        let server = MockServer::start(Duration::ZERO, |n| if n < 2 { status(503) } else { OK });
+        let client = server.client().retries(3).build();
+
+        assert!(fetch(&client).is_ok());
+        assert_eq!(server.requests(), 3);
+    }
+
+    #[test]
+    fn gives_up_after_retries() {
+        let server = MockServer::start(Duration::ZERO, |_| status(500));
+        let client = server.client().retries(2).build();
+
+        let error = fetch(&client).unwrap_err();
+        assert!(matches!(error, ArchidektError::Status { status: 500, .. }));
+        assert_eq!(server.requests(), 3);
+    }
+
+    #[test]
+    fn doesnt_retry_private_collections() {
+        let server = MockServer::start(Duration::ZERO, |_| status(404));
+        let client = server.client().retries(3).build();
+
+        let error = fetch(&client).unwrap_err();
+        assert!(matches!(error, ArchidektError::Private { status: 404 }));
+        assert_eq!(server.requests(), 1);
+    }
+
+    #[test]
+    fn waits_for_retry_after() {
+        let server = MockServer::start(Duration::ZERO, |n| {
+            if n == 0 {
+                Reply {
+                    status: 429,
+                    headers: "Retry-After: 1\r\n",
+                    body: "",
+                }
+            } else {
+                OK
+            }
+        });
+        let client = server.client().retries(1).build();
+
+        let start = Instant::now();
+        assert!(fetch(&client).is_ok());
+        assert!(start.elapsed() >= Duration::from_secs(1));
+        assert_eq!(server.requests(), 2);
+    }
+
+    #[test]
+    fn caps_the_backoff() {
+        let server = MockServer::start(Duration::ZERO, |_| status(500));
+        let client = server
+            .client()
+            .retries(40)
+            .max_delay(Duration::from_millis(5))
+            .build();
+
+        let start = Instant::now();
+        assert!(fetch(&client).is_err());
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert_eq!(server.requests(), 41);
+    }
+
+    #[test]
+    fn caps_retry_after() {
+        let server = MockServer::start(Duration::ZERO, |n| {
+            if n == 0 {
+                Reply {
+                    status: 503,
+                    headers: "Retry-After: 999999999\r\n",
+                    body: "",
+                }
+            } else {
+                OK
+            }
+        });
+        let client = server
+            .client()
+            .retries(1)
+            .max_delay(Duration::from_millis(50))
+            .build();
+
+        let start = Instant::now();
+        assert!(fetch(&client).is_ok());
+        assert!(start.elapsed() < Duration::from_secs(5));
+        assert_eq!(server.requests(), 2);
+    }
+
+    #[test]
+    fn limits_concurrency() {
+        let server = MockServer::start(Duration::from_millis(100), |_| OK);
+        let client = server.client().concurrency(2).build();
+
+        std::thread::scope(|scope| {
+            for _ in 0..6 {
+                let client = client.clone();
+                scope.spawn(move || fetch(&client).unwrap());
+            }
+        });
+        assert_eq!(server.requests(), 6);
+        assert_eq!(server.peak.load(Ordering::SeqCst), 2);
+    }
+}
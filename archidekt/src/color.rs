@@ -7,7 +7,48 @@ use winnow::error::{AddContext, ContextError, ParseError, StrContext};
 use winnow::stream::Stream;
 use winnow::Parser;
 
-#[derive(Default, Debug, Clone, Copy, serde::Serialize)]
+/// Names of color combinations, the first name of a combination is the one displayed
+const NAMES: [(&str, &str); 37] = [
+    ("Colorless", ""),
+    ("C", ""),
+    ("White", "W"),
+    ("Blue", "U"),
+    ("Black", "B"),
+    ("Red", "R"),
+    ("Green", "G"),
+    ("Azorius", "WU"),
+    ("Dimir", "UB"),
+    ("Rakdos", "BR"),
+    ("Gruul", "RG"),
+    ("Selesnya", "WG"),
+    ("Orzhov", "WB"),
+    ("Izzet", "UR"),
+    ("Golgari", "BG"),
+    ("Boros", "WR"),
+    ("Simic", "UG"),
+    ("Bant", "WUG"),
+    ("Esper", "WUB"),
+    ("Grixis", "UBR"),
+    ("Jund", "BRG"),
+    ("Naya", "WRG"),
+    ("Abzan", "WBG"),
+    ("Jeskai", "WUR"),
+    ("Sultai", "UBG"),
+    ("Mardu", "WBR"),
+    ("Temur", "URG"),
+    ("Yore-Tiller", "WUBR"),
+    ("Glint-Eye", "UBRG"),
+    ("Dune-Brood", "WBRG"),
+    ("Ink-Treader", "WURG"),
+    ("Witch-Maw", "WUBG"),
+    ("Artifice", "WUBR"),
+    ("Chaos", "UBRG"),
+    ("Aggression", "WBRG"),
+    ("Altruism", "WURG"),
+    ("Growth", "WUBG"),
+];
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize)]
 #[serde(into = "String")]
 pub struct ColorIdent([bool; 5]);
 
@@ -16,6 +57,39 @@ impl ColorIdent {
         Default::default()
     }
 
+    fn from_letters(letters: &str) -> Self {
+        let mut ident = Self::new();
+        for (i, c) in "WUBRG".chars().enumerate() {
+            ident[i] = letters.contains(c);
+        }
+        ident
+    }
+
+    /// The name of the combination, e.g. "Sultai" for `UBG`
+    pub fn name(&self) -> &'static str {
+        NAMES
+            .iter()
+            .find(|(_, letters)| Self::from_letters(letters) == *self)
+            .map_or("Five-Color", |(name, _)| name)
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        let normalize = |s: &str| -> String {
+            s.chars()
+                .filter(|c| c.is_alphanumeric())
+                .flat_map(char::to_lowercase)
+                .collect()
+        };
+        let name = normalize(name);
+        if ["fivecolor", "fivecolour", "wubrg"].contains(&name.as_str()) {
+            return Some(Self([true; 5]));
+        }
+        NAMES
+            .iter()
+            .find(|(candidate, _)| normalize(candidate) == name)
+            .map(|(_, letters)| Self::from_letters(letters))
+    }
+
     pub fn contains(&self, other: &Self) -> bool {
         /*
          * If color is always an ordered set of bools then given a target T
@@ -29,6 +103,29 @@ impl ColorIdent {
          */
         self.0.iter().zip(other.0.iter()).all(|(&t, &c)| t || !c)
     }
+
+    /// Every color of `self` is also in `other`, e.g. a card fitting a deck of `other`'s colors
+    pub fn is_subset(&self, other: &Self) -> bool {
+        other.contains(self)
+    }
+
+    /// Every color of `other` is also in `self`
+    pub fn is_superset(&self, other: &Self) -> bool {
+        self.contains(other)
+    }
+
+    /// `self` and `other` share at least one color
+    pub fn intersects(&self, other: &Self) -> bool {
+        self.0.iter().zip(other.0.iter()).any(|(&a, &b)| a && b)
+    }
+
+    pub fn count(&self) -> usize {
+        self.0.iter().filter(|c| **c).count()
+    }
+
+    pub fn is_colorless(&self) -> bool {
+        self.count() == 0
+    }
 }
 
 impl std::ops::Deref for ColorIdent {
@@ -43,13 +140,37 @@ impl std::ops::DerefMut for ColorIdent {
     }
 }
 
+/// Writes the colors as letters in WUBRG order, or `C` when colorless
+impl std::fmt::Display for ColorIdent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if !self.iter().any(|c| *c) {
+            return f.write_str("C");
+        }
+        for (_, c) in self.iter().zip("WUBRG".chars()).filter(|(ok, _)| **ok) {
+            write!(f, "{c}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses letters (`BGU`), comma separated colors (`Black,Green`) or combination names (`Sultai`)
+impl std::str::FromStr for ColorIdent {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(ident) = Self::from_name(s) {
+            return Ok(ident);
+        }
+        let compact: String = s.split_whitespace().collect();
+        ColorIdentParser
+            .parse(compact.as_str())
+            .map_err(|_| format!("unknown color identity \"{s}\""))
+    }
+}
+
 impl From<ColorIdent> for String {
     fn from(value: ColorIdent) -> Self {
-        value
-            .iter()
-            .zip("WUBRG".chars())
-            .filter_map(|(ok, c)| ok.then_some(c))
-            .collect()
+        value.to_string()
     }
 }
 
@@ -122,10 +243,84 @@ impl<'de> Visitor<'de> for ColorIdentParser {
         formatter.write_str("string or sequence")
     }
 
-    fn visit_str<E>(mut self, value: &str) -> Result<Self::Value, E>
+    fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
     where
         E: de::Error,
     {
-        self.parse(value).map_err(|e| E::custom(e.to_string()))
+        value.parse().map_err(E::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(s: &str) -> ColorIdent {
+        let ident: ColorIdent = s.parse().unwrap();
+        assert_eq!(ident.to_string().parse::<ColorIdent>(), Ok(ident), "{s}");
+        ident
+    }
+
+    #[test]
+    fn names_round_trip() {
+        for (name, letters) in NAMES {
+            assert_eq!(
+                round_trip(name),
+                ColorIdent::from_letters(letters),
+                "{name}"
+            );
+        }
+        assert_eq!(round_trip("Five-Color"), ColorIdent([true; 5]));
+    }
+
+    #[test]
+    fn letters_round_trip() {
+        for bits in 0..32 {
+            let ident = ColorIdent(std::array::from_fn(|i| bits & 1 << i != 0));
+            let letters: String = "WUBRG"
+                .chars()
+                .enumerate()
+                .filter(|(i, _)| ident[*i])
+                .map(|(_, c)| c)
+                .collect();
+            assert_eq!(round_trip(&letters), ident, "{letters}");
+        }
+    }
+
+    #[test]
+    fn colorless_is_c() {
+        assert_eq!(ColorIdent::new().to_string(), "C");
+        assert_eq!(round_trip(""), ColorIdent::new());
+        assert_eq!(round_trip("C"), ColorIdent::new());
+        assert_eq!(round_trip("Colorless"), ColorIdent::new());
+    }
+
+    #[test]
+    fn set_operations() {
+        let (sultai, golgari) = (
+            ColorIdent::from_letters("UBG"),
+            ColorIdent::from_letters("BG"),
+        );
+        let (red, colorless) = (ColorIdent::from_letters("R"), ColorIdent::new());
+
+        assert!(golgari.is_subset(&sultai) && sultai.is_superset(&golgari));
+        assert!(!sultai.is_subset(&golgari) && !golgari.is_superset(&sultai));
+        assert!(sultai.is_subset(&sultai) && sultai.is_superset(&sultai));
+        assert!(colorless.is_subset(&sultai) && sultai.is_superset(&colorless));
+        assert!(!red.is_subset(&sultai));
+
+        assert!(golgari.intersects(&sultai));
+        assert!(!red.intersects(&sultai));
+        assert!(!colorless.intersects(&sultai) && !colorless.intersects(&colorless));
+    }
+
+    #[test]
+    fn parses_any_order_and_case() {
+        let sultai = ColorIdent::from_letters("UBG");
+        assert_eq!("bgu".parse(), Ok(sultai));
+        assert_eq!("Black, Green, Blue".parse(), Ok(sultai));
+        assert_eq!("sultai".parse(), Ok(sultai));
+        assert_eq!(sultai.to_string(), "UBG");
+        assert!("Purple".parse::<ColorIdent>().is_err());
     }
 }
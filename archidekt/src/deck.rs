@@ -0,0 +1,203 @@
+use strum::VariantArray;
+
+use crate::User;
+
+/// A deck on Archidekt, with only the cards that are actually in it
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Deck {
+    pub id: u32,
+    pub name: String,
+    pub owner: User,
+    pub cards: Vec<DeckCard>,
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct DeckCard {
+    pub quantity: u32,
+    pub name: String,
+    pub set: String,
+    pub scryfall: String,
+}
+
+/// Any public deck, e.g. one someone is brewing, with every card including the maybeboard
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeckList {
+    pub id: u32,
+    pub name: String,
+    /// Username of whoever built the deck
+    pub owner: String,
+    /// The owner, if they're in the pod
+    pub member: Option<User>,
+    pub cards: Vec<ListedCard>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListedCard {
+    pub card: DeckCard,
+    /// The primary category, e.g. "Ramp" or "Maybeboard"
+    pub category: Option<String>,
+    /// Whether the category counts towards the deck, false for the maybeboard and sideboard
+    pub in_deck: bool,
+}
+
+impl DeckList {
+    /// One `quantity name` line per card, leaving out the maybeboard and sideboard unless `boards`
+    pub fn wantlist(&self, boards: bool) -> String {
+        self.cards
+            .iter()
+            .filter(|listed| boards || listed.in_deck)
+            .map(|listed| format!("{} {}\n", listed.card.quantity, listed.card.name))
+            .collect()
+    }
+}
+
+/// Finds the deck ID in a deck URL like `archidekt.com/decks/12345/name`, or a bare ID
+pub fn parse_deck_id(input: &str) -> Option<u32> {
+    let input = input.trim();
+    let id = match input.find("decks/") {
+        Some(i) => &input[i + "decks/".len()..],
+        None => input,
+    };
+    let digits = id.find(|c: char| !c.is_ascii_digit()).unwrap_or(id.len());
+    id[..digits].parse().ok()
+}
+
+/// Where the API serves a deck, relative to the site
+pub(crate) fn deck_path(id: u32) -> String {
+    format!("/api/decks/{id}/")
+}
+
+/// One page of a user's deck listing
+#[derive(Debug, serde::Deserialize)]
+pub(crate) struct DeckPage {
+    pub next: Option<String>,
+    pub results: Vec<DeckSummary>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub(crate) struct DeckSummary {
+    pub id: u32,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub(crate) struct RawDeck {
+    pub id: u32,
+    pub name: String,
+    #[serde(default)]
+    pub owner: Option<RawOwner>,
+    #[serde(default)]
+    pub categories: Vec<RawCategory>,
+    pub cards: Vec<RawDeckCard>,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub(crate) struct RawOwner {
+    pub id: u32,
+    pub username: String,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub(crate) struct RawCategory {
+    pub name: String,
+    #[serde(rename = "includedInDeck")]
+    pub included: bool,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub(crate) struct RawDeckCard {
+    pub quantity: u32,
+    #[serde(default)]
+    pub categories: Option<Vec<String>>,
+    pub card: RawCard,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub(crate) struct RawCard {
+    pub uid: String,
+    #[serde(rename = "oracleCard")]
+    pub oracle: RawOracle,
+    pub edition: RawEdition,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub(crate) struct RawOracle {
+    pub name: String,
+}
+
+#[derive(Debug, serde::Deserialize)]
+pub(crate) struct RawEdition {
+    #[serde(rename = "editioncode")]
+    pub code: String,
+}
+
+impl RawDeck {
+    /// Every card with its primary category, cards in categories left out of the deck (like
+    /// the maybeboard) are marked as such
+    fn listed(&self) -> Vec<ListedCard> {
+        let excluded: Vec<&str> = self
+            .categories
+            .iter()
+            .filter(|category| !category.included)
+            .map(|category| category.name.as_str())
+            .collect();
+
+        self.cards
+            .iter()
+            .map(|card| {
+                let category = card
+                    .categories
+                    .as_ref()
+                    .and_then(|categories| categories.first())
+                    .cloned();
+                ListedCard {
+                    in_deck: category
+                        .as_ref()
+                        .is_none_or(|category| !excluded.contains(&category.as_str())),
+                    category,
+                    card: DeckCard {
+                        quantity: card.quantity,
+                        name: card.card.oracle.name.clone(),
+                        set: card.card.edition.code.to_lowercase(),
+                        scryfall: card.card.uid.clone(),
+                    },
+                }
+            })
+            .collect()
+    }
+
+    /// Leaves out cards that aren't part of the deck, like the maybeboard
+    pub(crate) fn into_deck(self, owner: User) -> Deck {
+        Deck {
+            cards: self
+                .listed()
+                .into_iter()
+                .filter(|listed| listed.in_deck)
+                .map(|listed| listed.card)
+                .collect(),
+            id: self.id,
+            name: self.name,
+            owner,
+        }
+    }
+
+    pub(crate) fn into_list(self) -> DeckList {
+        let (owner, member) = match &self.owner {
+            Some(owner) => (
+                owner.username.clone(),
+                User::VARIANTS
+                    .iter()
+                    .find(|user| user.id() == owner.id)
+                    .copied(),
+            ),
+            None => (String::new(), None),
+        };
+
+        DeckList {
+            cards: self.listed(),
+            id: self.id,
+            name: self.name,
+            owner,
+            member,
+        }
+    }
+}
@@ -0,0 +1,66 @@
+use std::fmt;
+
+/// Everything that can go wrong talking to Archidekt
+#[derive(Debug)]
+pub enum ArchidektError {
+    /// No response at all, e.g. no connection or a DNS failure
+    Network(String),
+    /// The collection is private or doesn't exist
+    Private { status: u16 },
+    /// Any other unsuccessful status
+    Status { status: u16, text: String },
+    /// The JSON envelope around the export couldn't be encoded or decoded
+    Json(serde_json::Error),
+    /// The header of the exported CSV couldn't be read, rows that fail to parse are skipped instead
+    Csv(csv::Error),
+}
+
+impl ArchidektError {
+    pub(crate) fn from_status(status: u16, text: &str) -> Self {
+        match status {
+            401 | 403 | 404 => ArchidektError::Private { status },
+            _ => ArchidektError::Status {
+                status,
+                text: text.to_string(),
+            },
+        }
+    }
+}
+
+impl fmt::Display for ArchidektError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArchidektError::Network(e) => {
+                write!(f, "Could not reach Archidekt, check the connection ({e})")
+            }
+            ArchidektError::Private { status } => write!(
+                f,
+                "The collection is private or doesn't exist, make it public on Archidekt (HTTP {status})"
+            ),
+            ArchidektError::Status { status: 429, .. } => {
+                write!(f, "Rate limited by Archidekt, try again in a minute (HTTP 429)")
+            }
+            ArchidektError::Status { status, text } => {
+                write!(f, "Archidekt answered HTTP {status} {text}")
+            }
+            ArchidektError::Json(e) => write!(f, "Unexpected response from Archidekt: {e}"),
+            ArchidektError::Csv(e) => write!(f, "Unreadable export from Archidekt: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for ArchidektError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ArchidektError::Json(e) => Some(e),
+            ArchidektError::Csv(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<serde_json::Error> for ArchidektError {
+    fn from(value: serde_json::Error) -> Self {
+        ArchidektError::Json(value)
+    }
+}
@@ -0,0 +1,298 @@
+use std::collections::HashMap;
+
+use crate::Entry;
+
+/// File name of the price history store, relative to the assets directory
+pub const HISTORY_FILE: &str = "price_history.csv";
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct PricePoint {
+    /// Days since the unix epoch
+    pub day: u32,
+    pub price: f32,
+}
+
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Row {
+    scryfall: String,
+    day: u32,
+    price: f32,
+}
+
+#[derive(Debug, Clone)]
+pub struct Mover {
+    pub scryfall: String,
+    pub from: f32,
+    pub to: f32,
+}
+
+impl Mover {
+    pub fn delta(&self) -> f32 {
+        self.to - self.from
+    }
+}
+
+/// Cardmarket prices per Scryfall ID over time.
+///
+/// A point is only stored when the price differs from the previous one, so a series reads as "the
+/// price was `p` from day `d` until the next point".
+#[derive(Debug, Default, Clone)]
+pub struct PriceHistory {
+    series: HashMap<String, Vec<PricePoint>>,
+}
+
+impl PriceHistory {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn from_reader(reader: impl std::io::Read) -> anyhow::Result<Self> {
+        let mut history = Self::new();
+        for row in csv::Reader::from_reader(reader).deserialize::<Row>() {
+            let Row {
+                scryfall,
+                day,
+                price,
+            } = row?;
+            history.push(scryfall, PricePoint { day, price });
+        }
+        for points in history.series.values_mut() {
+            points.sort_by_key(|point| point.day);
+        }
+        Ok(history)
+    }
+
+    pub fn to_writer(&self, writer: impl std::io::Write) -> anyhow::Result<()> {
+        let mut wrt = csv::Writer::from_writer(writer);
+        let mut ids: Vec<_> = self.series.keys().collect();
+        ids.sort();
+        for id in ids {
+            for point in &self.series[id] {
+                wrt.serialize(Row {
+                    scryfall: id.clone(),
+                    day: point.day,
+                    price: point.price,
+                })?;
+            }
+        }
+        wrt.flush()?;
+        Ok(())
+    }
+
+    /// Appends the current prices of `entries` as seen on `day`, returns whether any changed
+    pub fn record<'a>(&mut self, day: u32, entries: impl IntoIterator<Item = &'a Entry>) -> bool {
+        let mut changed = false;
+        for entry in entries {
+            if let Some(price) = entry.price.filter(|_| !entry.scryfall.is_empty()) {
+                changed |= self.push(entry.scryfall.clone(), PricePoint { day, price });
+            }
+        }
+        changed
+    }
+
+    /// Returns whether the series changed
+    fn push(&mut self, scryfall: String, point: PricePoint) -> bool {
+        let points = self.series.entry(scryfall).or_default();
+        match points.last_mut() {
+            Some(last) if last.price == point.price => return false,
+            Some(last) if last.day == point.day => {
+                last.price = point.price;
+                // Back to what it was before today, so today's point says nothing new
+                if points.len() > 1 && points[points.len() - 2].price == point.price {
+                    points.pop();
+                }
+            }
+            _ => points.push(point),
+        }
+        true
+    }
+
+    pub fn series(&self, scryfall: &str) -> &[PricePoint] {
+        self.series.get(scryfall).map_or(&[], Vec::as_slice)
+    }
+
+    /// The most recent day anything was recorded
+    pub fn latest(&self) -> Option<u32> {
+        self.series
+            .values()
+            .filter_map(|points| points.last())
+            .map(|point| point.day)
+            .max()
+    }
+
+    /// The price of a card as it was on `day`
+    pub fn price_at(&self, scryfall: &str, day: u32) -> Option<f32> {
+        self.series(scryfall)
+            .iter()
+            .take_while(|point| point.day <= day)
+            .last()
+            .map(|point| point.price)
+    }
+
+    /// Price changes over the last `days` days, largest absolute change first
+    pub fn movers<'a>(&self, ids: impl IntoIterator<Item = &'a str>, days: u32) -> Vec<Mover> {
+        let Some(latest) = self.latest() else {
+            return Vec::new();
+        };
+        let since = latest.saturating_sub(days);
+
+        let mut seen = std::collections::HashSet::new();
+        let mut movers: Vec<Mover> = ids
+            .into_iter()
+            .filter(|id| seen.insert(*id))
+            .filter_map(|id| {
+                let from = self.price_at(id, since)?;
+                let to = self.series(id).last()?.price;
+                (from != to).then(|| Mover {
+                    scryfall: id.to_string(),
+                    from,
+                    to,
+                })
+            })
+            .collect();
+
+        movers.sort_by(|a, b| b.delta().abs().total_cmp(&a.delta().abs()));
+        movers
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+/// Days since the unix epoch
+pub fn today() -> u32 {
+    let secs = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    (secs / (60 * 60 * 24)) as u32
+}
+
+/// Formats days since the unix epoch as `YYYY-MM-DD`
+pub fn date(day: u32) -> String {
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = day as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    let y = yoe + era * 400 + i64::from(m <= 2);
+    format!("{y:04}-{m:02}-{d:02}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ColorIdent, User};
+
+    fn entry(scryfall: &str, price: Option<f32>) -> Entry {
+        Entry {
+            owner: User::Strosel.into(),
+            quantity: 1,
+            color_identity: ColorIdent::new(),
+            name: "Sol Ring".into(),
+            ty: String::new(),
+            set: "c21".into(),
+            scryfall: scryfall.into(),
+            price,
+        }
+    }
+
+    fn days(history: &PriceHistory, scryfall: &str) -> Vec<(u32, f32)> {
+        history
+            .series(scryfall)
+            .iter()
+            .map(|point| (point.day, point.price))
+            .collect()
+    }
+
+    #[test]
+    fn only_records_changes() {
+        let mut history = PriceHistory::new();
+        assert!(history.record(1, &[entry("a", Some(1.0))]));
+        assert!(!history.record(2, &[entry("a", Some(1.0))]));
+        assert!(history.record(3, &[entry("a", Some(2.0))]));
+        assert!(!history.record(4, &[entry("a", Some(2.0)), entry("a", Some(2.0))]));
+
+        assert_eq!(days(&history, "a"), [(1, 1.0), (3, 2.0)]);
+    }
+
+    #[test]
+    fn same_day_replaces() {
+        let mut history = PriceHistory::new();
+        history.record(1, &[entry("a", Some(1.0))]);
+        history.record(2, &[entry("a", Some(2.0))]);
+        assert!(history.record(2, &[entry("a", Some(3.0))]));
+        assert_eq!(days(&history, "a"), [(1, 1.0), (2, 3.0)]);
+
+        assert!(history.record(2, &[entry("a", Some(1.0))]));
+        assert_eq!(days(&history, "a"), [(1, 1.0)]);
+    }
+
+    #[test]
+    fn skips_unpriced_and_unknown_cards() {
+        let mut history = PriceHistory::new();
+        assert!(!history.record(1, &[entry("a", None), entry("", Some(1.0))]));
+        assert_eq!(history.latest(), None);
+    }
+
+    #[test]
+    fn price_at_holds_until_the_next_point() {
+        let mut history = PriceHistory::new();
+        history.record(10, &[entry("a", Some(1.0))]);
+        history.record(20, &[entry("a", Some(2.0))]);
+
+        assert_eq!(history.price_at("a", 9), None);
+        assert_eq!(history.price_at("a", 10), Some(1.0));
+        assert_eq!(history.price_at("a", 19), Some(1.0));
+        assert_eq!(history.price_at("a", 25), Some(2.0));
+        assert_eq!(history.price_at("b", 25), None);
+    }
+
+    #[test]
+    fn movers_biggest_change_first() {
+        let mut history = PriceHistory::new();
+        let day_one = [
+            entry("a", Some(1.0)),
+            entry("b", Some(10.0)),
+            entry("c", Some(5.0)),
+        ];
+        history.record(1, &day_one);
+        // No price when the window starts, so it can't have moved
+        history.record(2, &[entry("d", Some(1.0))]);
+        history.record(8, &[entry("a", Some(2.0)), entry("b", Some(4.0))]);
+        history.record(10, &[entry("c", Some(5.5)), entry("d", Some(9.0))]);
+
+        let movers = history.movers(["a", "b", "c", "a", "d"], 9);
+        let moved: Vec<(&str, f32)> = movers
+            .iter()
+            .map(|mover| (mover.scryfall.as_str(), mover.delta()))
+            .collect();
+        assert_eq!(moved, [("b", -6.0), ("a", 1.0), ("c", 0.5)]);
+
+        // Only "c" moved within the last day
+        let recent = history.movers(["a", "b", "c"], 1);
+        assert_eq!(recent.len(), 1);
+        assert_eq!(recent[0].scryfall, "c");
+    }
+
+    #[test]
+    fn round_trips_through_csv() {
+        let mut history = PriceHistory::new();
+        history.record(1, &[entry("a", Some(1.5)), entry("b", Some(2.0))]);
+        history.record(3, &[entry("a", Some(1.25))]);
+
+        let mut csv = Vec::new();
+        history.to_writer(&mut csv).unwrap();
+        let read = PriceHistory::from_reader(csv.as_slice()).unwrap();
+
+        assert_eq!(days(&read, "a"), [(1, 1.5), (3, 1.25)]);
+        assert_eq!(days(&read, "b"), [(1, 2.0)]);
+    }
+
+    #[test]
+    fn formats_dates() {
+        assert_eq!(date(0), "1970-01-01");
+        assert_eq!(date(19_782), "2024-02-29");
+    }
+}
@@ -0,0 +1,344 @@
+use strum::{Display, EnumString, VariantArray};
+
+use crate::{ColorIdent, Entry, Owner, Parsed};
+
+/// CSV export formats of other collection managers
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    Hash,
+    Display,
+    EnumString,
+    VariantArray,
+    serde::Serialize,
+    serde::Deserialize,
+)]
+pub enum CsvFormat {
+    Moxfield,
+    Deckbox,
+    ManaBox,
+    DragonShield,
+}
+
+impl CsvFormat {
+    pub fn columns(&self) -> ColumnMap {
+        let col = |name: &str| Some(name.to_string());
+        match self {
+            CsvFormat::Moxfield => ColumnMap {
+                quantity: col("Count"),
+                name: "Name".into(),
+                set: col("Edition"),
+                ..Default::default()
+            },
+            CsvFormat::Deckbox => ColumnMap {
+                quantity: col("Count"),
+                name: "Name".into(),
+                set: col("Edition Code"),
+                ty: col("Type"),
+                color: col("Color"),
+                price: col("Price"),
+                ..Default::default()
+            },
+            CsvFormat::ManaBox => ColumnMap {
+                quantity: col("Quantity"),
+                name: "Name".into(),
+                set: col("Set code"),
+                scryfall: col("Scryfall ID"),
+                // Only has what the owner paid, which isn't what the card is worth
+                ..Default::default()
+            },
+            CsvFormat::DragonShield => ColumnMap {
+                quantity: col("Quantity"),
+                name: "Card Name".into(),
+                set: col("Set Code"),
+                price: col("MARKET"),
+                ..Default::default()
+            },
+        }
+    }
+
+    pub fn read(&self, owner: Owner, reader: impl std::io::Read) -> anyhow::Result<Parsed> {
+        self.columns().read(owner, reader)
+    }
+
+    /// Finds the format whose identifying columns are all present in `headers`
+    pub fn detect(headers: &[String]) -> Option<Self> {
+        // Moxfield exports are a subset of the Deckbox layout so it must be tried last
+        [
+            Self::DragonShield,
+            Self::ManaBox,
+            Self::Deckbox,
+            Self::Moxfield,
+        ]
+        .into_iter()
+        .find(|format| format.columns().fits(headers))
+    }
+}
+
+/// Reads the header row of a CSV export
+pub fn headers(data: &str) -> anyhow::Result<Vec<String>> {
+    let mut reader = csv::Reader::from_reader(strip_separator(data).as_bytes());
+    Ok(reader
+        .headers()?
+        .iter()
+        .map(|header| header.trim().to_string())
+        .collect())
+}
+
+/// Dragon Shield (and Excel) prefix the export with a separator hint
+fn strip_separator(data: &str) -> &str {
+    match data.split_once('\n') {
+        Some((first, rest)) if first.trim().starts_with("sep=") => rest,
+        _ => data,
+    }
+}
+
+/// Which CSV header holds which `Entry` field, fields without a column are left empty
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ColumnMap {
+    pub quantity: Option<String>,
+    pub name: String,
+    pub set: Option<String>,
+    pub scryfall: Option<String>,
+    pub price: Option<String>,
+    pub ty: Option<String>,
+    pub color: Option<String>,
+}
+
+impl ColumnMap {
+    /// Guesses a mapping from common header names
+    pub fn guess(headers: &[String]) -> Self {
+        let find = |names: &[&str]| {
+            headers
+                .iter()
+                .find(|header| names.iter().any(|name| header.eq_ignore_ascii_case(name)))
+                .cloned()
+        };
+
+        Self {
+            quantity: find(&["quantity", "count", "qty", "amount"]),
+            name: find(&["name", "card name", "card"]).unwrap_or_default(),
+            set: find(&["set code", "edition code", "set", "edition"]),
+            scryfall: find(&["scryfall id", "scryfall"]),
+            price: find(&["price", "market", "price (card market)"]),
+            ty: find(&["type", "types", "type line"]),
+            color: find(&["color identity", "identities", "color"]),
+        }
+    }
+
+    /// Whether the columns identifying a card are among `headers`
+    ///
+    /// Price, type and color depend on the export settings, so they are read when present and
+    /// don't count here
+    pub fn fits(&self, headers: &[String]) -> bool {
+        std::iter::once(&self.name)
+            .chain(
+                [&self.quantity, &self.set, &self.scryfall]
+                    .into_iter()
+                    .flatten(),
+            )
+            .all(|column| headers.contains(column))
+    }
+
+    /// Reads the rows of an export, rows that can't be parsed are skipped
+    pub fn read(&self, owner: Owner, mut reader: impl std::io::Read) -> anyhow::Result<Parsed> {
+        let mut data = String::new();
+        reader.read_to_string(&mut data)?;
+
+        let mut reader = csv::ReaderBuilder::new()
+            .flexible(true)
+            .from_reader(strip_separator(&data).as_bytes());
+        let headers = reader.headers()?.clone();
+        let find = |column: &String| headers.iter().position(|header| header.trim() == column);
+        let index = |column: &Option<String>| -> anyhow::Result<Option<usize>> {
+            column
+                .as_ref()
+                .map(|column| {
+                    find(column).ok_or_else(|| anyhow::anyhow!("Missing column \"{column}\""))
+                })
+                .transpose()
+        };
+        // Left empty when the export was made without them, see `fits`
+        let optional = |column: &Option<String>| column.as_ref().and_then(find);
+
+        let name = index(&Some(self.name.clone()))?.unwrap();
+        let quantity = index(&self.quantity)?;
+        let set = index(&self.set)?;
+        let scryfall = index(&self.scryfall)?;
+        let price = optional(&self.price);
+        let ty = optional(&self.ty);
+        let color = optional(&self.color);
+
+        Ok(Parsed::from_rows(reader.records().map(
+            |record| -> anyhow::Result<Entry> {
+                let record = record?;
+                let field = |i: Option<usize>| {
+                    i.and_then(|i| record.get(i))
+                        .unwrap_or_default()
+                        .trim()
+                        .to_string()
+                };
+
+                Ok(Entry {
+                    owner: owner.clone(),
+                    quantity: match quantity {
+                        Some(_) => field(quantity).parse()?,
+                        None => 1,
+                    },
+                    color_identity: parse_color(&field(color))?,
+                    name: field(Some(name)),
+                    ty: field(ty),
+                    set: field(set).to_lowercase(),
+                    scryfall: field(scryfall),
+                    price: parse_price(&field(price)),
+                })
+            },
+        )))
+    }
+}
+
+fn parse_color(color: &str) -> anyhow::Result<ColorIdent> {
+    color
+        .parse()
+        .map_err(|e| anyhow::anyhow!("Invalid color \"{color}\": {e}"))
+}
+
+/// Parses prices like `$1.50`, `1,50 €` or `$1,234.56`
+///
+/// The last `.` or `,` separates the decimals, unless it shows up more than once like in
+/// `1,234,567`, and other separators are dropped
+fn parse_price(price: &str) -> Option<f32> {
+    let kept: String = price
+        .chars()
+        .filter(|c| c.is_ascii_digit() || matches!(c, '.' | ','))
+        .collect();
+    let decimal = kept
+        .rfind(['.', ','])
+        .filter(|&i| kept.matches(&kept[i..=i]).count() == 1);
+    let digits = |s: &str| -> String { s.chars().filter(char::is_ascii_digit).collect() };
+    match decimal {
+        Some(i) => format!("{}.{}", digits(&kept[..i]), digits(&kept[i + 1..])),
+        None => digits(&kept),
+    }
+    .parse()
+    .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::User;
+
+    fn headers(row: &str) -> Vec<String> {
+        super::headers(row).unwrap()
+    }
+
+    #[test]
+    fn detects_each_format() {
+        let exports = [
+            (
+                "Count,Tradelist Count,Name,Edition,Condition,Language,Foil,Tags,Last Modified,\
+                 Collector Number,Alter,Proxy,Purchase Price",
+                CsvFormat::Moxfield,
+            ),
+            (
+                "Count,Tradelist Count,Name,Edition,Edition Code,Card Number,Condition,Language,\
+                 Foil,Signed,Type,Cost,Rarity,Price,Image URL,Last Updated",
+                CsvFormat::Deckbox,
+            ),
+            (
+                "Binder Name,Binder Type,Name,Set code,Set name,Collector number,Foil,Rarity,\
+                 Quantity,ManaBox ID,Scryfall ID,Purchase price,Misprint,Altered,Condition,\
+                 Language,Purchase price currency",
+                CsvFormat::ManaBox,
+            ),
+            (
+                "sep=,\nFolder Name,Quantity,Trade Quantity,Card Name,Set Code,Set Name,\
+                 Card Number,Condition,Printing,Language,Price Bought,Date Bought,LOW,MID,MARKET",
+                CsvFormat::DragonShield,
+            ),
+        ];
+        for (row, format) in exports {
+            assert_eq!(CsvFormat::detect(&headers(row)), Some(format), "{format}");
+        }
+        assert_eq!(CsvFormat::detect(&headers("Card,Amount")), None);
+    }
+
+    #[test]
+    fn reads_rows_and_skips_bad_ones() {
+        let data = [
+            "Count,Name,Edition Code,Type,Color,Price",
+            "2,Sol Ring,C21,Artifact,,$1.50",
+            "x,Mind Stone,C21,Artifact,,$0.50",
+            "1,Birds of Paradise,M12,Creature,Purple,$5.00",
+            "1,Llanowar Elves,M19,Creature,G,",
+        ]
+        .join("\n");
+        let owner = Owner::Guest("Guest".into());
+        let parsed = CsvFormat::Deckbox
+            .read(owner.clone(), data.as_bytes())
+            .unwrap();
+
+        let read: Vec<_> = parsed
+            .entries
+            .iter()
+            .map(|entry| {
+                (
+                    entry.quantity,
+                    entry.name.as_str(),
+                    entry.set.as_str(),
+                    entry.price,
+                )
+            })
+            .collect();
+        assert_eq!(
+            read,
+            [
+                (2, "Sol Ring", "c21", Some(1.5)),
+                (1, "Llanowar Elves", "m19", None)
+            ]
+        );
+        assert!(parsed.entries.iter().all(|entry| entry.owner == owner));
+        assert_eq!(parsed.entries[1].color_identity.to_string(), "G");
+        assert_eq!(
+            parsed.skipped.iter().map(|row| row.row).collect::<Vec<_>>(),
+            [2, 3]
+        );
+    }
+
+    #[test]
+    fn reads_without_optional_columns() {
+        let data = "Count,Name,Edition Code\n3,Sol Ring,C21\n";
+        let parsed = CsvFormat::Deckbox
+            .read(User::Strosel.into(), data.as_bytes())
+            .unwrap();
+
+        assert_eq!(parsed.entries.len(), 1);
+        assert_eq!(parsed.entries[0].quantity, 3);
+        assert_eq!(parsed.entries[0].price, None);
+        assert!(parsed.entries[0].color_identity.is_colorless());
+    }
+
+    #[test]
+    fn needs_the_mapped_columns() {
+        let data = "Count,Name\n1,Sol Ring\n";
+        assert!(CsvFormat::Deckbox
+            .read(User::Strosel.into(), data.as_bytes())
+            .is_err());
+    }
+
+    #[test]
+    fn parses_prices() {
+        assert_eq!(parse_price("$1.50"), Some(1.5));
+        assert_eq!(parse_price("1,50 €"), Some(1.5));
+        assert_eq!(parse_price("$1,234.56"), Some(1234.56));
+        assert_eq!(parse_price("1.234,56 €"), Some(1234.56));
+        assert_eq!(parse_price("1,234,567"), Some(1234567.0));
+        assert_eq!(parse_price("12"), Some(12.0));
+        assert_eq!(parse_price(""), None);
+        assert_eq!(parse_price("N/A"), None);
+    }
+}
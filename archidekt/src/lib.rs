@@ -2,8 +2,34 @@
 
 use strum::{Display, FromRepr, VariantArray};
 
+pub const BASE_URL: &str = "https://archidekt.com";
+
+/// Directory under `assets` with each member's standing wantlist as `{user}.txt`
+pub const WANTS_DIR: &str = "wants";
+
+mod bundle;
+#[cfg(not(target_arch = "wasm32"))]
+mod client;
 mod color;
+mod deck;
+mod error;
+mod history;
+mod import;
+#[cfg(not(target_arch = "wasm32"))]
+mod source;
+pub use bundle::{Bundle, Unbundled, BUNDLE_FILE, BUNDLE_VERSION};
 pub use color::ColorIdent;
+pub use deck::{parse_deck_id, Deck, DeckCard, DeckList, ListedCard};
+pub use error::ArchidektError;
+pub use history::{date, Mover, PriceHistory, PricePoint, HISTORY_FILE};
+pub use import::{headers, ColumnMap, CsvFormat};
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use client::{ArchidektClient, ArchidektClientBuilder};
+#[cfg(not(target_arch = "wasm32"))]
+pub use history::today;
+#[cfg(not(target_arch = "wasm32"))]
+pub use source::{Archidekt, CollectionSource, CsvFile, Sources, SOURCES_FILE};
 
 #[derive(Debug, Clone, serde::Deserialize)]
 struct Response {
@@ -21,6 +47,8 @@ struct Response {
     PartialEq,
     Eq,
     Hash,
+    PartialOrd,
+    Ord,
     VariantArray,
     Display,
     FromRepr,
@@ -44,6 +72,37 @@ impl User {
     }
 }
 
+/// Who an entry belongs to, guests are temporary owners of collections imported for a session
+#[derive(
+    Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, serde::Serialize, serde::Deserialize,
+)]
+#[serde(untagged)]
+pub enum Owner {
+    Member(User),
+    Guest(String),
+}
+
+impl std::fmt::Display for Owner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Owner::Member(user) => user.fmt(f),
+            Owner::Guest(name) => name.fmt(f),
+        }
+    }
+}
+
+impl From<User> for Owner {
+    fn from(value: User) -> Self {
+        Owner::Member(value)
+    }
+}
+
+impl PartialEq<User> for Owner {
+    fn eq(&self, other: &User) -> bool {
+        matches!(self, Owner::Member(user) if user == other)
+    }
+}
+
 #[derive(serde::Serialize)]
 struct Body {
     fields: &'static [&'static str],
@@ -56,7 +115,7 @@ struct Body {
 #[derive(serde::Deserialize)]
 struct RawEntry {
     #[serde(rename = "Quantity")]
-    quantity: u8,
+    quantity: u32,
     #[serde(rename = "Identities")]
     color_identity: ColorIdent,
     #[serde(rename = "Name")]
@@ -70,53 +129,70 @@ struct RawEntry {
     #[serde(rename = "Scryfall ID")]
     scryfall: String,
     #[serde(rename = "Price (Card Market)")]
-    price: f32,
+    price: Option<f32>,
 }
 
 pub type Collection = Vec<Entry>;
+
+/// A row left out of a collection because it couldn't be parsed
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct SkippedRow {
+    /// Counting from the first row after the header
+    pub row: usize,
+    pub reason: String,
+}
+
+impl std::fmt::Display for SkippedRow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Row {}: {}", self.row, self.reason)
+    }
+}
+
+/// The rows of a collection that could be parsed, and the ones that couldn't
+#[derive(Debug, Default)]
+pub struct Parsed {
+    pub entries: Collection,
+    pub skipped: Vec<SkippedRow>,
+}
+
+impl Parsed {
+    /// Keeps the good rows of `rows`, recording why the others were skipped
+    pub fn from_rows<E: std::fmt::Display>(rows: impl Iterator<Item = Result<Entry, E>>) -> Self {
+        let mut parsed = Self::default();
+        for (i, row) in rows.enumerate() {
+            match row {
+                Ok(entry) => parsed.entries.push(entry),
+                Err(e) => parsed.skipped.push(SkippedRow {
+                    row: i + 1,
+                    reason: e.to_string(),
+                }),
+            }
+        }
+        parsed
+    }
+}
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct Entry {
-    pub owner: User,
-    pub quantity: u8,
+    pub owner: Owner,
+    pub quantity: u32,
     pub color_identity: ColorIdent,
     pub name: String,
     pub ty: String,
     pub set: String,
     pub scryfall: String,
-    pub price: f32,
+    pub price: Option<f32>,
 }
 
-impl Entry {
-    pub fn headers() -> impl Iterator<Item = &'static str> {
-        [
-            "Owner", "X", "Color Id", "Name", "Type", "Set", "Scryfall", "Price",
-        ]
-        .into_iter()
-    }
-
-    pub fn values(&self) -> impl Iterator<Item = (&'static str, String)> {
-        Self::headers().zip([
-            self.owner.to_string(),
-            self.quantity.to_string(),
-            self.color_identity.into(),
-            self.name.clone(),
-            self.ty.clone(),
-            self.set.clone(),
-            self.scryfall.clone(),
-            format!("{:.2}€", self.price),
-        ])
-    }
+#[cfg(not(target_arch = "wasm32"))]
+///Gets a user's collection with a default `ArchidektClient`
+pub fn get_collections(owner: &User) -> Result<Parsed, ArchidektError> {
+    ArchidektClient::new().collection(*owner)
 }
 
-#[cfg(not(target_arch = "wasm32"))]
-///Gets a user's collection as a CSV String
-pub fn get_collections(owner: &User) -> anyhow::Result<Collection> {
-    let req = ehttp::Request::json(
-        format!(
-            "https://archidekt.com/api/collection/export/v2/{}/",
-            owner.id()
-        ),
-        &Body {
+impl Body {
+    fn export() -> Self {
+        Body {
             fields: &[
                 "quantity",
                 "card__oracleCard__name",
@@ -130,18 +206,17 @@ pub fn get_collections(owner: &User) -> anyhow::Result<Collection> {
             page: 1,
             game: 1,
             size: 10000,
-        },
-    )?;
-
-    let data: Response = ehttp::fetch_blocking(&req)
-        .map_err(anyhow::Error::msg)?
-        .json()?;
+        }
+    }
+}
 
-    let mut reader = csv::Reader::from_reader(std::io::Cursor::new(data.content));
+/// Parses the CSV of a collection export, skipping rows that can't be parsed
+fn parse_export(owner: User, content: &str) -> Result<Parsed, ArchidektError> {
+    let mut reader = csv::Reader::from_reader(content.as_bytes());
+    reader.headers().map_err(ArchidektError::Csv)?;
 
-    let col = reader
-        .deserialize::<RawEntry>()
-        .map(|ent| {
+    let col = Parsed::from_rows(reader.deserialize::<RawEntry>().map(
+        |ent| -> Result<Entry, csv::Error> {
             let RawEntry {
                 quantity,
                 name,
@@ -151,7 +226,7 @@ pub fn get_collections(owner: &User) -> anyhow::Result<Collection> {
                 color_identity,
                 supertypes,
                 types,
-            } = ent.map_err(anyhow::Error::msg)?;
+            } = ent?;
             let ty = if supertypes.is_empty() {
                 types
             } else {
@@ -159,7 +234,7 @@ pub fn get_collections(owner: &User) -> anyhow::Result<Collection> {
             };
 
             Ok(Entry {
-                owner: *owner,
+                owner: owner.into(),
                 quantity,
                 color_identity,
                 name,
@@ -168,8 +243,8 @@ pub fn get_collections(owner: &User) -> anyhow::Result<Collection> {
                 scryfall,
                 price,
             })
-        })
-        .collect::<anyhow::Result<Vec<Entry>>>()?;
+        },
+    ));
 
     Ok(col)
 }
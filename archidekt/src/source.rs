@@ -0,0 +1,128 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::{ArchidektClient, CsvFormat, Parsed, User};
+
+/// File listing which source each pod member's collection comes from
+pub const SOURCES_FILE: &str = "sources.csv";
+
+/// Something that can produce the collection of a pod member
+pub trait CollectionSource: Send + Sync {
+    fn fetch(&self, owner: User) -> anyhow::Result<Parsed>;
+}
+
+/// The owner's collection on archidekt.com
+#[derive(Default)]
+pub struct Archidekt(pub ArchidektClient);
+
+impl CollectionSource for Archidekt {
+    fn fetch(&self, owner: User) -> anyhow::Result<Parsed> {
+        Ok(self.0.collection(owner)?)
+    }
+}
+
+/// A collection exported to a CSV file by another collection manager
+pub struct CsvFile {
+    pub format: CsvFormat,
+    pub path: PathBuf,
+}
+
+impl CollectionSource for CsvFile {
+    fn fetch(&self, owner: User) -> anyhow::Result<Parsed> {
+        let file = std::fs::File::open(&self.path)
+            .map_err(|e| anyhow::anyhow!("Failed opening {}: {e}", self.path.display()))?;
+        self.format.read(owner.into(), file)
+    }
+}
+
+#[derive(serde::Deserialize)]
+struct Row {
+    owner: User,
+    /// `Archidekt` or one of the `CsvFormat`s
+    source: String,
+    path: Option<PathBuf>,
+}
+
+/// The source of each pod member's collection, members not listed use Archidekt
+#[derive(Default)]
+pub struct Sources {
+    archidekt: Archidekt,
+    sources: HashMap<User, Box<dyn CollectionSource>>,
+}
+
+impl Sources {
+    pub fn new(client: ArchidektClient) -> Self {
+        Self {
+            archidekt: Archidekt(client),
+            sources: HashMap::new(),
+        }
+    }
+
+    /// Reads a `owner,source,path` CSV, e.g. `Strosel,ManaBox,collections/strosel.csv`
+    pub fn from_reader(
+        reader: impl std::io::Read,
+        client: ArchidektClient,
+    ) -> anyhow::Result<Self> {
+        let mut sources = Self::new(client);
+        for row in csv::Reader::from_reader(reader).deserialize::<Row>() {
+            let Row {
+                owner,
+                source,
+                path,
+            } = row?;
+
+            let source: Box<dyn CollectionSource> = if source == "Archidekt" {
+                Box::new(Archidekt(sources.archidekt.0.clone()))
+            } else {
+                let format = source
+                    .parse()
+                    .map_err(|_| anyhow::anyhow!("Unknown source \"{source}\" for {owner}"))?;
+                let path = path.ok_or_else(|| anyhow::anyhow!("Missing path for {owner}"))?;
+                Box::new(CsvFile { format, path })
+            };
+            sources.insert(owner, source);
+        }
+        Ok(sources)
+    }
+
+    /// Reads `SOURCES_FILE` if it exists
+    pub fn load(client: ArchidektClient) -> anyhow::Result<Self> {
+        match std::fs::File::open(SOURCES_FILE) {
+            Ok(file) => Self::from_reader(file, client),
+            Err(_) => Ok(Self::new(client)),
+        }
+    }
+
+    pub fn insert(&mut self, owner: User, source: Box<dyn CollectionSource>) {
+        self.sources.insert(owner, source);
+    }
+
+    pub fn get(&self, owner: User) -> &dyn CollectionSource {
+        self.sources
+            .get(&owner)
+            .map_or(&self.archidekt, |source| &**source)
+    }
+
+    pub fn fetch(&self, owner: User) -> anyhow::Result<Parsed> {
+        self.get(owner).fetch(owner)
+    }
+
+    /// Fetches several owners in parallel, the client's concurrency limit still applies
+    pub fn fetch_all(&self, owners: &[User]) -> Vec<(User, anyhow::Result<Parsed>)> {
+        std::thread::scope(|scope| {
+            let handles: Vec<_> = owners
+                .iter()
+                .map(|owner| (*owner, scope.spawn(|| self.fetch(*owner))))
+                .collect();
+            handles
+                .into_iter()
+                .map(|(owner, handle)| {
+                    let fetched = handle
+                        .join()
+                        .unwrap_or_else(|_| Err(anyhow::anyhow!("Fetching {owner} panicked")));
+                    (owner, fetched)
+                })
+                .collect()
+        })
+    }
+}
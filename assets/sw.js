@@ -1,12 +1,12 @@
-var cacheName = 'egui-template-pwa';
+var cacheName = 'mtg-searcher-pwa';
+/* Collection snapshots are stored by the app itself, see src/storage.rs */
+var dataCacheName = 'mtg-searcher-data';
 var filesToCache = [
   './',
   './index.html',
-  './eframe_template.js',
-  './eframe_template_bg.wasm',
 ];
 
-/* Start the service worker and cache all of the app's content */
+/* Start the service worker and cache the app shell */
 self.addEventListener('install', function (e) {
   e.waitUntil(
     caches.open(cacheName).then(function (cache) {
@@ -15,11 +15,49 @@ self.addEventListener('install', function (e) {
   );
 });
 
-/* Serve cached content when offline */
+/* Drop caches left behind by older versions */
+self.addEventListener('activate', function (e) {
+  e.waitUntil(
+    caches.keys().then(function (names) {
+      return Promise.all(
+        names
+          .filter(function (name) {
+            return name !== cacheName && name !== dataCacheName;
+          })
+          .map(function (name) {
+            return caches.delete(name);
+          })
+      );
+    })
+  );
+});
+
+/* Serve the app from the network when possible and from the cache when offline */
 self.addEventListener('fetch', function (e) {
+  var url = new URL(e.request.url);
+  if (
+    e.request.method !== 'GET' ||
+    url.origin !== self.location.origin ||
+    /\/assets\/.*\.(bin|csv)$/.test(url.pathname)
+  ) {
+    return;
+  }
+
   e.respondWith(
-    caches.match(e.request).then(function (response) {
-      return response || fetch(e.request);
-    })
+    fetch(e.request)
+      .then(function (response) {
+        if (response.ok) {
+          var copy = response.clone();
+          caches.open(cacheName).then(function (cache) {
+            cache.put(e.request, copy);
+          });
+        }
+        return response;
+      })
+      .catch(function () {
+        return caches.open(cacheName).then(function (cache) {
+          return cache.match(e.request);
+        });
+      })
   );
 });
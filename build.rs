@@ -4,17 +4,65 @@ use strum::VariantArray;
 fn main() {
     let target = std::env::var("CARGO_CFG_TARGET_ARCH");
     if matches!(target.as_ref().map(|x| &**x), Ok("wasm32")) {
-        for user in User::VARIANTS {
-            let data = get_collections(&user).expect(&format!("Error fetching user: {user}"));
+        let history_path = format!("assets/{HISTORY_FILE}");
+        let mut history = std::fs::File::open(&history_path)
+            .map_or_else(|_| Ok(PriceHistory::new()), PriceHistory::from_reader)
+            .expect("Error reading price history");
+        let day = today();
+        let client = ArchidektClient::builder()
+            .user_agent(concat!("mtg-searcher/", env!("CARGO_PKG_VERSION")))
+            .build();
+        let mut decks: std::collections::HashMap<_, _> =
+            client.all_decks(User::VARIANTS).into_iter().collect();
+        let sources = Sources::load(client).expect("Error reading collection sources");
+        let created = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs());
+        let mut bundle = Bundle::new(created);
+
+        for (user, fetched) in sources.fetch_all(User::VARIANTS) {
+            let decks = decks
+                .remove(&user)
+                .unwrap_or_else(|| Ok(Vec::new()))
+                .map_err(|e| {
+                    println!("cargo:warning=No decks for {user}: {e}");
+                    e.to_string()
+                });
+            let wants = std::fs::read_to_string(format!("assets/{WANTS_DIR}/{user}.txt"))
+                .unwrap_or_default();
+            let Parsed { entries, skipped } = match fetched {
+                Ok(parsed) => parsed,
+                Err(e) => {
+                    println!("cargo:warning=Skipping {user}: {e}");
+                    bundle.push(user, Err(e.to_string()), decks, wants);
+                    continue;
+                }
+            };
 
             let mut wrt = csv::Writer::from_path(format!("assets/{user}.csv"))
-                .expect(&format!("Error creating writer for {user}"));
-            for entry in data {
+                .unwrap_or_else(|_| panic!("Error creating writer for {user}"));
+            for row in &skipped {
+                println!("cargo:warning={user}: skipped {row}");
+            }
+
+            for entry in entries.iter() {
                 eprintln!("{entry:?}");
                 wrt.serialize(entry).unwrap();
             }
             wrt.flush()
-                .expect(&format!("Error flushing writer for {user}"));
+                .unwrap_or_else(|_| panic!("Error flushing writer for {user}"));
+
+            history.record(day, &entries);
+            bundle.push(user, Ok(&Parsed { entries, skipped }), decks, wants);
         }
+
+        let bytes = bundle.to_bytes().expect("Error encoding collection bundle");
+        std::fs::write(format!("assets/{BUNDLE_FILE}"), bytes)
+            .expect("Error writing collection bundle");
+
+        let file = std::fs::File::create(&history_path).expect("Error creating price history");
+        history
+            .to_writer(file)
+            .expect("Error writing price history");
     }
 }
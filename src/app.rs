@@ -1,12 +1,29 @@
+use crate::cart::{Cart, CartWindow};
+use crate::chart;
 use crate::collection::*;
-use archidekt::{Collection, Entry, User};
+use crate::columns::{ColumnKind, Columns};
+use crate::decks::DeckUsage;
+use crate::group::{self, Group, GroupKey, Grouped};
+use crate::import::{DeckImport, Import, CAN_IMPORT_DECKS};
+use crate::index::SearchIndex;
+use crate::plan::Plan;
+use crate::saved::{self, SavedSearch, SavedWindow};
+use crate::stats::{Stats, Tally};
+use crate::trades::{self, Trade};
+use crate::wants::{Match, Wants};
+use archidekt::{Collection, Deck, Entry, Mover, Owner, PriceHistory, User};
+use std::collections::{HashMap, HashSet};
+use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
 use strum::VariantArray;
 
 use eframe::egui::{self, Color32};
-use egui_extras::{Column, TableBuilder};
+use egui_extras::{Column, TableBuilder, TableRow};
 
-fn color_code_user(value: User) -> egui::Color32 {
-    match value {
+fn color_code_user(value: &Owner) -> egui::Color32 {
+    let Owner::Member(user) = value else {
+        return Color32::GRAY;
+    };
+    match user {
         User::Strosel => Color32::from_rgb(0xbe, 0x18, 0x5d),
         User::Amon8808 => Color32::from_rgb(0x03, 0x69, 0xa1),
         User::MathIsMath => Color32::from_rgb(0x0f, 0x76, 0x6e),
@@ -18,13 +35,63 @@ fn color_code_user(value: User) -> egui::Color32 {
     }
 }
 
+/// The owner filter as one chip per member and guest, clicking cycles between showing only them,
+/// hiding them and neither
+fn owner_chips(ui: &mut egui::Ui, owners: &mut Owners, guests: &[Owner]) {
+    ui.horizontal_wrapped(|ui| {
+        ui.label("Owners:");
+        let everyone = User::VARIANTS.iter().map(|user| Owner::from(*user));
+        for owner in everyone.chain(guests.iter().cloned()) {
+            let color = color_code_user(&owner);
+            let text = egui::RichText::new(owner.to_string());
+            let (chip, hover) = if owners.include.contains(&owner) {
+                (
+                    egui::Button::new(text.color(Color32::WHITE)).fill(color),
+                    "Only showing their cards among others picked, click to hide them",
+                )
+            } else if owners.exclude.contains(&owner) {
+                (
+                    egui::Button::new(text.strikethrough().color(color)).fill(Color32::TRANSPARENT),
+                    "Hiding their cards, click to reset",
+                )
+            } else {
+                (
+                    egui::Button::new(text.color(color)).stroke(egui::Stroke::new(1.0, color)),
+                    "Click to only show their cards and anyone else picked",
+                )
+            };
+            if ui
+                .add(chip.corner_radius(8.0))
+                .on_hover_text(hover)
+                .clicked()
+            {
+                owners.cycle(owner);
+            }
+        }
+        if !owners.is_everyone() && ui.small_button("Everyone").clicked() {
+            *owners = Owners::default();
+        }
+    });
+}
+
+fn mana_color(color: char) -> egui::Color32 {
+    match color {
+        'W' => Color32::from_rgb(251, 246, 211),
+        'U' => Color32::from_rgb(150, 196, 212),
+        'B' => Color32::from_rgb(176, 168, 163),
+        'R' => Color32::from_rgb(216, 143, 115),
+        'G' => Color32::from_rgb(147, 203, 164),
+        _ => Color32::from_rgb(204, 194, 192),
+    }
+}
+
 fn color_ident(color: char) -> egui::RichText {
     match color {
-        'W' => egui::RichText::new("\u{e600}").color(Color32::from_rgb(251, 246, 211)),
-        'U' => egui::RichText::new("\u{e601}").color(Color32::from_rgb(150, 196, 212)),
-        'B' => egui::RichText::new("\u{e602}").color(Color32::from_rgb(176, 168, 163)),
-        'R' => egui::RichText::new("\u{e603}").color(Color32::from_rgb(216, 143, 115)),
-        'G' => egui::RichText::new("\u{e604}").color(Color32::from_rgb(147, 203, 164)),
+        'W' => egui::RichText::new("\u{e600}").color(mana_color(color)),
+        'U' => egui::RichText::new("\u{e601}").color(mana_color(color)),
+        'B' => egui::RichText::new("\u{e602}").color(mana_color(color)),
+        'R' => egui::RichText::new("\u{e603}").color(mana_color(color)),
+        'G' => egui::RichText::new("\u{e604}").color(mana_color(color)),
         _ => egui::RichText::new("\u{e904}"),
     }
     .font(egui::FontId {
@@ -33,24 +100,493 @@ fn color_ident(color: char) -> egui::RichText {
     })
 }
 
+/// `None` for cards without a Scryfall ID, like those of most guest imports
+fn card_image(scryfall: &str) -> Option<String> {
+    let mut chars = scryfall.chars();
+    let (first, second) = (chars.next()?, chars.next()?);
+    Some(format!(
+        "https://cards.scryfall.io/png/front/{first}/{second}/{scryfall}.png"
+    ))
+}
+
+/// File name and contents of a file to import
+type File = (String, Vec<u8>);
+
+/// Lets the user save an HTML page, as a download on the web
+fn save_html(name: &str, html: String) {
+    #[cfg(not(target_arch = "wasm32"))]
+    if let Some(path) = rfd::FileDialog::new()
+        .add_filter("HTML", &["html"])
+        .set_file_name(name)
+        .save_file()
+    {
+        if let Err(e) = std::fs::write(&path, html) {
+            log::error!("Failed writing {}: {e}", path.display());
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    {
+        let name = name.to_string();
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Some(file) = rfd::AsyncFileDialog::new()
+                .add_filter("HTML", &["html"])
+                .set_file_name(name)
+                .save_file()
+                .await
+            {
+                if let Err(e) = file.write(html.as_bytes()).await {
+                    log::error!("Failed saving {}: {e}", file.file_name());
+                }
+            }
+        });
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+enum View {
+    Cards,
+    Movers,
+    Stats,
+    Matches,
+    Trades,
+}
+
+/// UI state kept between sessions
+#[derive(serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+struct Persisted {
+    search: Search,
+    dedup: bool,
+    across_owners: bool,
+    only_free: bool,
+    view: View,
+    movers_days: u32,
+    /// Who uses the app on this device
+    me: Option<User>,
+    exclude_mine: bool,
+    columns: Columns,
+}
+
+impl Default for Persisted {
+    fn default() -> Self {
+        Self {
+            search: Search::single(),
+            dedup: false,
+            across_owners: false,
+            only_free: false,
+            view: View::Cards,
+            movers_days: 7,
+            me: None,
+            exclude_mine: false,
+            columns: Columns::default(),
+        }
+    }
+}
+
+/// How the app starts out, apart from the collections themselves
+#[derive(Default)]
+pub struct Startup {
+    pub freshness: Freshness,
+    /// The pending check for a newer snapshot, if any
+    pub updates: Option<Receiver<Update>>,
+    /// The search and whether to group printings, restored from a shared link
+    pub search: Option<(Search, bool)>,
+}
+
+/// A row of the cards table, as indices into the collection
+enum Row {
+    Entry(usize),
+    Group(Grouped),
+    /// One of the entries of an expanded group
+    Child(usize),
+}
+
+/// What the cells of the cards table read and change
+struct Cells<'a> {
+    /// The visible columns in order
+    columns: &'a [ColumnKind],
+    usage: &'a DeckUsage,
+    wants: &'a Wants,
+    detail: &'a mut Option<Entry>,
+    cart: &'a mut Cart,
+    expanded: &'a mut HashSet<GroupKey>,
+    /// Whether a group was expanded or collapsed, so the rows need rebuilding
+    toggled: bool,
+}
+
+impl Cells<'_> {
+    fn entry(&mut self, row: &mut TableRow, entry: &Entry, child: bool) {
+        for &kind in self.columns {
+            row.col(|ui| self.entry_cell(ui, kind, entry, child));
+        }
+    }
+
+    fn entry_cell(&mut self, ui: &mut egui::Ui, kind: ColumnKind, entry: &Entry, child: bool) {
+        match kind {
+            ColumnKind::Pick => {
+                let mut picked = self.cart.contains(entry);
+                if ui
+                    .checkbox(&mut picked, "")
+                    .on_hover_text("Add to the pull lists")
+                    .changed()
+                {
+                    self.cart.toggle(entry);
+                }
+            }
+            ColumnKind::Owner => {
+                ui.colored_label(color_code_user(&entry.owner), entry.owner.to_string());
+            }
+            ColumnKind::Quantity => {
+                ui.label(entry.quantity.to_string());
+            }
+            ColumnKind::ColorId => {
+                ui.horizontal(|ui| {
+                    ui.style_mut().spacing.item_spacing = egui::vec2(1.0, 1.0);
+                    for c in String::from(entry.color_identity).chars() {
+                        ui.label(color_ident(c));
+                    }
+                })
+                .response
+                .on_hover_text(entry.color_identity.name());
+            }
+            ColumnKind::Name => {
+                ui.horizontal(|ui| {
+                    if child {
+                        ui.weak("↳");
+                    }
+                    if ui.link(&entry.name).clicked() {
+                        *self.detail = Some(entry.clone());
+                    }
+                    self.wanted(ui, entry);
+                });
+            }
+            ColumnKind::Type => {
+                ui.label(&entry.ty);
+            }
+            ColumnKind::Set => {
+                ui.label(&entry.set);
+            }
+            ColumnKind::Scryfall => {
+                let Some(image) = card_image(&entry.scryfall) else {
+                    return;
+                };
+                ui.hyperlink_to(
+                    &entry.scryfall,
+                    format!(
+                        "https://scryfall.com/search?q=scryfall_id%3A{}",
+                        entry.scryfall
+                    ),
+                )
+                .on_hover_ui(|ui| {
+                    ui.add(egui::Image::new(image).fit_to_exact_size(egui::vec2(292.0, 408.0)));
+                });
+            }
+            ColumnKind::Price => {
+                if let Some(price) = entry.price {
+                    ui.label(format!("{price:.2}€"));
+                }
+            }
+            ColumnKind::Free => {
+                let used = self.usage.get(entry);
+                let free = self.usage.free(entry);
+                if free < entry.quantity {
+                    ui.colored_label(ui.visuals().warn_fg_color, free.to_string())
+                } else {
+                    ui.label(free.to_string())
+                }
+                .on_hover_text(used.map_or_else(
+                    || "Not in any deck".to_string(),
+                    |used| {
+                        format!(
+                            "{} of {} copies across printings are in decks",
+                            used.in_decks, used.owned
+                        )
+                    },
+                ));
+            }
+            ColumnKind::Decks => {
+                if let Some(used) = self.usage.get(entry) {
+                    let names: Vec<&str> =
+                        used.decks.iter().map(|(name, _)| name.as_str()).collect();
+                    ui.label(names.join(", ")).on_hover_ui(|ui| {
+                        for (name, quantity) in &used.decks {
+                            ui.label(format!("{quantity}× {name}"));
+                        }
+                    });
+                }
+            }
+        }
+    }
+
+    fn group(&mut self, row: &mut TableRow, group: &Group) {
+        for &kind in self.columns {
+            row.col(|ui| self.group_cell(ui, kind, group));
+        }
+    }
+
+    fn group_cell(&mut self, ui: &mut egui::Ui, kind: ColumnKind, group: &Group) {
+        let first = group.first();
+        match kind {
+            ColumnKind::Pick => {
+                let mut picked = group.entries.iter().all(|entry| self.cart.contains(entry));
+                if ui
+                    .checkbox(&mut picked, "")
+                    .on_hover_text("Add every printing to the pull lists")
+                    .changed()
+                {
+                    for entry in &group.entries {
+                        if self.cart.contains(entry) != picked {
+                            self.cart.toggle(entry);
+                        }
+                    }
+                }
+            }
+            ColumnKind::Owner => {
+                if let Some(owner) = group.owner {
+                    ui.colored_label(color_code_user(owner), owner.to_string());
+                    return;
+                }
+                ui.horizontal(|ui| {
+                    ui.style_mut().spacing.item_spacing = egui::vec2(2.0, 2.0);
+                    for (owner, quantity) in group.owners() {
+                        egui::Frame::new()
+                            .fill(color_code_user(owner))
+                            .corner_radius(6.0)
+                            .inner_margin(egui::Margin::symmetric(4, 0))
+                            .show(ui, |ui| {
+                                ui.label(
+                                    egui::RichText::new(quantity.to_string()).color(Color32::WHITE),
+                                );
+                            })
+                            .response
+                            .on_hover_text(format!("{owner}: {quantity}"));
+                    }
+                });
+            }
+            ColumnKind::Quantity => {
+                ui.label(group.quantity().to_string());
+            }
+            ColumnKind::ColorId => {
+                ui.horizontal(|ui| {
+                    ui.style_mut().spacing.item_spacing = egui::vec2(1.0, 1.0);
+                    for c in String::from(first.color_identity).chars() {
+                        ui.label(color_ident(c));
+                    }
+                })
+                .response
+                .on_hover_text(first.color_identity.name());
+            }
+            ColumnKind::Name => {
+                ui.horizontal(|ui| {
+                    let key = group.key();
+                    let open = self.expanded.contains(&key);
+                    if ui
+                        .small_button(if open { "▼" } else { "▶" })
+                        .on_hover_text(format!("{} printings", group.entries.len()))
+                        .clicked()
+                    {
+                        if open {
+                            self.expanded.remove(&key);
+                        } else {
+                            self.expanded.insert(key);
+                        }
+                        self.toggled = true;
+                    }
+                    if ui.link(&first.name).clicked() {
+                        *self.detail = Some(first.clone());
+                    }
+                    self.wanted(ui, first);
+                });
+            }
+            ColumnKind::Type => {
+                ui.label(&first.ty);
+            }
+            ColumnKind::Set => {
+                let sets = group.sets().join(", ");
+                ui.label(&sets).on_hover_text(sets);
+            }
+            ColumnKind::Scryfall => {}
+            ColumnKind::Price => {
+                match group.prices() {
+                    Some((min, max)) if min < max => ui.label(format!("{min:.2}–{max:.2}€")),
+                    Some((price, _)) => ui.label(format!("{price:.2}€")),
+                    None => ui.label(""),
+                };
+            }
+            ColumnKind::Free => {
+                let free = group.free(self.usage);
+                if free < group.quantity() {
+                    ui.colored_label(ui.visuals().warn_fg_color, free.to_string());
+                } else {
+                    ui.label(free.to_string());
+                }
+            }
+            ColumnKind::Decks => {
+                let mut decks: Vec<(&Owner, &str, u32)> = Vec::new();
+                for (owner, _) in group.owners() {
+                    let used = group
+                        .entries
+                        .iter()
+                        .find(|entry| entry.owner == *owner)
+                        .and_then(|entry| self.usage.get(entry));
+                    for (name, quantity) in used.into_iter().flat_map(|used| &used.decks) {
+                        decks.push((owner, name, *quantity));
+                    }
+                }
+                if !decks.is_empty() {
+                    let names: Vec<&str> = decks.iter().map(|(_, name, _)| *name).collect();
+                    ui.label(names.join(", ")).on_hover_ui(|ui| {
+                        for (owner, name, quantity) in &decks {
+                            ui.colored_label(
+                                color_code_user(owner),
+                                format!("{quantity}× {name} ({owner})"),
+                            );
+                        }
+                    });
+                }
+            }
+        }
+    }
+
+    /// A heart for each member who wants the card
+    fn wanted(&self, ui: &mut egui::Ui, entry: &Entry) {
+        for wanter in self.wants.wanted_by(entry) {
+            ui.colored_label(color_code_user(&wanter.into()), "♥")
+                .on_hover_text(format!("{wanter} wants this"));
+        }
+    }
+}
+
 pub struct App {
     data: Collection,
+    history: PriceHistory,
+    issues: LoadIssues,
     search: Search,
+    decks: Vec<Deck>,
+    wants: Wants,
+    /// Who has cards someone else wants, rebuilt along with the index
+    matches: Vec<Match>,
+    /// Suggested trades, rebuilt along with the matches
+    trades: Vec<Trade>,
+    index: SearchIndex,
+    usage: DeckUsage,
+    /// Indices into `data` matching `searched`
+    results: Vec<usize>,
+    /// The search, `only_free` and hidden owner `results` were computed for, `None` when they
+    /// are stale
+    searched: Option<(Search, bool, Option<User>)>,
+    /// Stats of `results`, computed when the Stats view first needs them
+    stats: Option<Stats>,
+    /// Price changes of `results` over how many days, as indices into `data`
+    movers: Option<(u32, Vec<(usize, Mover)>)>,
+    /// The table rows of `results` and the `dedup` and `across_owners` they were built with,
+    /// `None` when they are stale
+    rows: Option<(bool, bool, Vec<Row>)>,
     dedup: bool,
+    /// Group printings of the whole pod rather than per owner
+    across_owners: bool,
+    /// The grouped rows showing their entries
+    expanded: HashSet<GroupKey>,
+    /// Hide cards all of whose copies are in their owner's decks
+    only_free: bool,
+    /// Who uses the app on this device, their rows are highlighted
+    me: Option<User>,
+    /// Hide the cards of `me`
+    exclude_mine: bool,
+    /// The layout of the cards table
+    columns: Columns,
+    view: View,
+    movers_days: u32,
+    detail: Option<Entry>,
+    import: Option<Import>,
+    /// Picked files arrive asynchronously on the web
+    files: (Sender<File>, Receiver<File>),
+    freshness: Freshness,
+    /// The pending check for a newer snapshot, if any
+    updates: Option<Receiver<Update>>,
+    saved: Vec<SavedSearch>,
+    saved_window: Option<SavedWindow>,
+    deck_import: DeckImport,
+    /// Who to borrow the wantlist from, shown in a window until closed
+    plan: Option<Plan>,
+    cart: Cart,
+    cart_window: Option<CartWindow>,
+    /// The search last written to the address bar
+    #[cfg(target_arch = "wasm32")]
+    shared: Option<(Search, bool)>,
 }
 
 impl App {
-    pub fn new(data: Collection) -> Self {
+    fn new(
+        Loaded {
+            data,
+            decks,
+            wants,
+            issues,
+        }: Loaded,
+        history: PriceHistory,
+        startup: Startup,
+        persisted: Persisted,
+        saved: Vec<SavedSearch>,
+    ) -> Self {
+        // A shared link takes precedence over the search from last time
+        let (search, dedup) = startup
+            .search
+            .unwrap_or((persisted.search, persisted.dedup));
+        let mut wants = Wants::new(wants);
+        wants.index(&data);
+        let usage = DeckUsage::new(&data, &decks);
+        let matches = wants.matches(&data);
+
         Self {
+            index: SearchIndex::new(&data),
+            trades: trades::find(&data, &matches, &usage),
+            usage,
+            matches,
             data,
-            search: Search::single(),
-            dedup: false,
+            decks,
+            wants,
+            history,
+            issues,
+            search,
+            results: Vec::new(),
+            searched: None,
+            stats: None,
+            movers: None,
+            rows: None,
+            dedup,
+            across_owners: persisted.across_owners,
+            expanded: HashSet::new(),
+            only_free: persisted.only_free,
+            me: persisted.me,
+            exclude_mine: persisted.exclude_mine,
+            columns: persisted.columns.repair(),
+            view: persisted.view,
+            movers_days: persisted.movers_days,
+            detail: None,
+            import: None,
+            files: channel(),
+            freshness: startup.freshness,
+            updates: startup.updates,
+            saved,
+            saved_window: None,
+            deck_import: DeckImport::default(),
+            plan: None,
+            cart: Cart::default(),
+            cart_window: None,
+            #[cfg(target_arch = "wasm32")]
+            shared: None,
         }
     }
 
-    pub fn creator(data: Collection) -> eframe::AppCreator<'static> {
+    pub fn creator(
+        loaded: Loaded,
+        history: PriceHistory,
+        startup: Startup,
+    ) -> eframe::AppCreator<'static> {
         use super::loader;
-        Box::new(|cc| {
+        Box::new(move |cc| {
             // This gives us image support:
             egui_extras::install_image_loaders(&cc.egui_ctx);
 
@@ -59,81 +595,1022 @@ impl App {
 
             loader::load_fonts(&cc.egui_ctx);
 
-            Ok(Box::new(App::new(data)))
+            let persisted = cc
+                .storage
+                .and_then(|storage| eframe::get_value(storage, eframe::APP_KEY))
+                .unwrap_or_default();
+            let saved = cc.storage.map(saved::load).unwrap_or_default();
+
+            Ok(Box::new(App::new(
+                loaded, history, startup, persisted, saved,
+            )))
         })
     }
 
-    fn mk_table(&self, ui: &mut egui::Ui) {
+    /// Swaps in a newer snapshot once the background check finds one
+    fn receive_update(&mut self, ctx: &egui::Context) {
+        let Some(updates) = &self.updates else {
+            return;
+        };
+
+        match updates.try_recv() {
+            Ok(Update::Refreshed {
+                loaded:
+                    Loaded {
+                        mut data,
+                        decks,
+                        wants,
+                        mut issues,
+                    },
+                history,
+            }) => {
+                // Guests were imported into the old snapshot and have to be carried over
+                let is_guest = |owner: &Owner| matches!(owner, Owner::Guest(_));
+                data.extend(self.data.drain(..).filter(|entry| is_guest(&entry.owner)));
+                issues.extend(self.issues.drain(..).filter(|(owner, _)| is_guest(owner)));
+
+                self.data = data;
+                self.decks = decks;
+                self.wants = Wants::new(wants);
+                self.issues = issues;
+                self.history = history;
+                self.freshness = Freshness::Live;
+                self.updates = None;
+                self.reindex();
+            }
+            Ok(Update::UpToDate) => {
+                self.freshness = Freshness::Live;
+                self.updates = None;
+            }
+            Ok(Update::Offline) | Err(TryRecvError::Disconnected) => {
+                if let Freshness::Checking { created } = self.freshness {
+                    self.freshness = Freshness::Offline { created };
+                }
+                self.updates = None;
+            }
+            Err(TryRecvError::Empty) => {
+                ctx.request_repaint_after(std::time::Duration::from_millis(250));
+            }
+        }
+    }
+
+    /// Keeps the address bar in sync with the search so it can be shared
+    #[cfg(target_arch = "wasm32")]
+    fn share_search(&mut self) {
+        let synced = self
+            .shared
+            .as_ref()
+            .is_some_and(|(search, dedup)| *search == self.search && *dedup == self.dedup);
+        if !synced {
+            crate::share::set_fragment(&crate::share::to_fragment(&self.search, self.dedup));
+            self.shared = Some((self.search.clone(), self.dedup));
+        }
+    }
+
+    /// Must be called after `data` changes
+    fn reindex(&mut self) {
+        self.index = SearchIndex::new(&self.data);
+        self.usage = DeckUsage::new(&self.data, &self.decks);
+        self.wants.index(&self.data);
+        self.matches = self.wants.matches(&self.data);
+        self.trades = trades::find(&self.data, &self.matches, &self.usage);
+        self.cart.sync(&self.data);
+        self.searched = None;
+    }
+
+    /// The member whose cards are hidden, if any
+    fn hidden(&self) -> Option<User> {
+        self.me.filter(|_| self.exclude_mine)
+    }
+
+    /// Reruns the search only when it changed since the last frame
+    fn refresh(&mut self) {
+        let hidden = self.hidden();
+        let stale = self
+            .searched
+            .as_ref()
+            .is_none_or(|(search, only_free, searched_hidden)| {
+                *search != self.search || *only_free != self.only_free || *searched_hidden != hidden
+            });
+        if stale {
+            self.results = self.index.search(&self.data, &self.search);
+            if self.only_free {
+                self.results.retain(|&i| self.usage.free(&self.data[i]) > 0);
+            }
+            if let Some(hidden) = hidden {
+                self.results.retain(|&i| self.data[i].owner != hidden);
+            }
+            self.searched = Some((self.search.clone(), self.only_free, hidden));
+            self.stats = None;
+            self.movers = None;
+            self.rows = None;
+        }
+        let grouped = self.rows.as_ref().is_some_and(|(dedup, across_owners, _)| {
+            *dedup == self.dedup && *across_owners == self.across_owners
+        });
+        if !grouped {
+            self.rows = Some((self.dedup, self.across_owners, self.mk_rows()));
+        }
+    }
+
+    /// Lays out `results` as table rows, grouped if `dedup`
+    fn mk_rows(&self) -> Vec<Row> {
+        if !self.dedup {
+            return self.results.iter().copied().map(Row::Entry).collect();
+        }
+        let mut rows = Vec::new();
+        for group in group::group(&self.data, &self.results, self.across_owners) {
+            if group.entries.len() == 1 {
+                rows.push(Row::Entry(group.entries[0]));
+                continue;
+            }
+            let children = if self.expanded.contains(&group.resolve(&self.data).key()) {
+                group.entries.clone()
+            } else {
+                Vec::new()
+            };
+            rows.push(Row::Group(group));
+            rows.extend(children.into_iter().map(Row::Child));
+        }
+        rows
+    }
+
+    fn results(&self) -> impl Iterator<Item = &Entry> {
+        self.results.iter().map(|&i| &self.data[i])
+    }
+
+    fn mk_table(&mut self, ui: &mut egui::Ui) {
+        let kinds: Vec<ColumnKind> = self.columns.visible().map(|column| column.kind).collect();
+        // A new layout gets a table of its own, or it would keep the old one's widths
+        let mut table = TableBuilder::new(ui)
+            .id_salt(("cards", &kinds))
+            .resizable(true)
+            .striped(true);
+        for column in self.columns.visible() {
+            table = table.column(Column::initial(column.width).at_least(20.0).clip(true));
+        }
+
+        let mut moved = None;
+        let mut toggled = false;
+        table
+            .header(20.0, |mut header| {
+                for &kind in &kinds {
+                    header.col(|ui| {
+                        self.columns.set_width(kind, ui.max_rect().width());
+                        let cell = ui.interact(
+                            ui.max_rect(),
+                            egui::Id::new(("column drop", kind)),
+                            egui::Sense::hover(),
+                        );
+                        ui.dnd_drag_source(egui::Id::new(("column", kind)), kind, |ui| {
+                            ui.strong(kind.title());
+                        })
+                        .response
+                        .on_hover_text("Drag to move the column");
+                        if cell.dnd_hover_payload::<ColumnKind>().is_some() {
+                            let rect = cell.rect;
+                            ui.painter().vline(
+                                rect.left(),
+                                rect.y_range(),
+                                ui.visuals().selection.stroke,
+                            );
+                        }
+                        if let Some(dragged) = cell.dnd_release_payload::<ColumnKind>() {
+                            moved = Some((*dragged, kind));
+                        }
+                    });
+                }
+            })
+            .body(|body| {
+                let Some((_, _, rows)) = &self.rows else {
+                    return;
+                };
+                let data = &self.data;
+                let mut cells = Cells {
+                    columns: &kinds,
+                    usage: &self.usage,
+                    wants: &self.wants,
+                    detail: &mut self.detail,
+                    cart: &mut self.cart,
+                    expanded: &mut self.expanded,
+                    toggled: false,
+                };
+                let me = self.me;
+                body.rows(20.0, rows.len(), |mut row| match &rows[row.index()] {
+                    &Row::Entry(i) => {
+                        let entry = &data[i];
+                        row.set_selected(me.is_some_and(|me| entry.owner == me));
+                        cells.entry(&mut row, entry, false);
+                    }
+                    &Row::Child(i) => {
+                        let entry = &data[i];
+                        row.set_selected(me.is_some_and(|me| entry.owner == me));
+                        cells.entry(&mut row, entry, true);
+                    }
+                    Row::Group(group) => {
+                        let group = group.resolve(data);
+                        row.set_selected(
+                            me.is_some_and(|me| group.owner.is_some_and(|owner| *owner == me)),
+                        );
+                        cells.group(&mut row, &group);
+                    }
+                });
+                toggled = cells.toggled;
+            });
+
+        if toggled {
+            self.rows = None;
+        }
+
+        if let Some((dragged, target)) = moved {
+            self.columns.move_to(dragged, target);
+        }
+    }
+
+    fn mk_movers(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.label("Over the last");
+            for days in [7, 30, 90] {
+                ui.selectable_value(&mut self.movers_days, days, format!("{days} days"));
+            }
+        });
+
+        if self
+            .movers
+            .as_ref()
+            .is_none_or(|(days, _)| *days != self.movers_days)
+        {
+            let entries: HashMap<&str, usize> = self
+                .results
+                .iter()
+                .map(|&i| (self.data[i].scryfall.as_str(), i))
+                .collect();
+            let movers = self
+                .history
+                .movers(entries.keys().copied(), self.movers_days)
+                .into_iter()
+                .map(|mover| (entries[mover.scryfall.as_str()], mover))
+                .collect();
+            self.movers = Some((self.movers_days, movers));
+        }
+        let Some((_, movers)) = &self.movers else {
+            return;
+        };
+
         let table = TableBuilder::new(ui)
             .resizable(false)
             .striped(true)
-            .column(Column::exact(90.0))
-            .column(Column::exact(20.0))
-            .column(Column::exact(100.0))
-            .column(Column::exact(200.0))
             .column(Column::exact(200.0))
             .column(Column::exact(50.0))
-            .column(Column::exact(250.0))
-            .column(Column::exact(70.0));
+            .column(Column::exact(70.0))
+            .column(Column::exact(70.0))
+            .column(Column::exact(120.0));
 
         table
             .header(20.0, |mut header| {
-                for hdr in Entry::headers() {
+                for hdr in ["Name", "Set", "From", "To", "Change"] {
                     header.col(|ui| {
                         ui.strong(hdr);
                     });
                 }
             })
             .body(|body| {
-                let data: Collection = self
-                    .data
+                body.rows(20.0, movers.len(), |mut row| {
+                    let (i, mover) = &movers[row.index()];
+                    let entry = &self.data[*i];
+
+                    row.col(|ui| {
+                        if ui.link(&entry.name).clicked() {
+                            self.detail = Some(entry.clone());
+                        }
+                    });
+                    row.col(|ui| {
+                        ui.label(&entry.set);
+                    });
+                    row.col(|ui| {
+                        ui.label(format!("{:.2}€", mover.from));
+                    });
+                    row.col(|ui| {
+                        ui.label(format!("{:.2}€", mover.to));
+                    });
+                    row.col(|ui| {
+                        let delta = mover.delta();
+                        let color = if delta > 0.0 {
+                            Color32::from_rgb(0x15, 0x80, 0x3d)
+                        } else {
+                            Color32::from_rgb(0xb9, 0x1c, 0x1c)
+                        };
+                        let percent = if mover.from > 0.0 {
+                            format!(" ({:+.0}%)", 100.0 * delta / mover.from)
+                        } else {
+                            String::new()
+                        };
+                        ui.colored_label(color, format!("{delta:+.2}€{percent}"));
+                    });
+                })
+            });
+    }
+
+    fn mk_stats(&mut self, ui: &mut egui::Ui) {
+        if self.stats.is_none() {
+            self.stats = Some(Stats::new(self.results()));
+        }
+        let Some(stats) = &self.stats else {
+            return;
+        };
+        let accent = ui.visuals().selection.bg_fill;
+        let euro = |value: f32| format!("{value:.2}€");
+        let count = |value: f32| format!("{value:.0}");
+
+        let mut detail = None;
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            ui.heading(format!(
+                "{} cards worth {:.2}€",
+                stats.total.cards, stats.total.value
+            ));
+
+            ui.columns(2, |cols| {
+                let owners = |f: fn(&Tally) -> f32| -> Vec<_> {
+                    stats
+                        .owners
+                        .iter()
+                        .map(|(owner, tally)| (owner.to_string(), f(tally), color_code_user(owner)))
+                        .collect()
+                };
+
+                cols[0].strong("Value per owner");
+                chart::bars(&mut cols[0], "owner_value", &owners(|t| t.value), euro);
+                cols[0].add_space(8.0);
+
+                cols[0].strong("Cards per owner");
+                chart::bars(
+                    &mut cols[0],
+                    "owner_cards",
+                    &owners(|t| t.cards as f32),
+                    count,
+                );
+                cols[0].add_space(8.0);
+
+                cols[0].strong("Color identity");
+                let colors: Vec<_> = stats
+                    .colors
                     .iter()
-                    .filter(|&data| self.search.apply(data))
-                    .dedup_cards(self.dedup)
+                    .zip("WUBRGC".chars())
+                    .map(|(n, c)| (c.to_string(), *n as f32, mana_color(c)))
                     .collect();
-                body.rows(20.0, data.len(), |mut row| {
-                    let row_index = row.index();
+                chart::bars(&mut cols[0], "colors", &colors, count);
 
-                    for (header, field) in data[row_index].values() {
-                        row.col(|ui| match header {
-                            "Owner" => {
-                                ui.colored_label(color_code_user(data[row_index].owner), field);
+                cols[1].strong("Card types");
+                let types: Vec<_> = stats
+                    .types
+                    .iter()
+                    .map(|(ty, n)| (ty.clone(), *n as f32, accent))
+                    .collect();
+                chart::bars(&mut cols[1], "types", &types, count);
+                cols[1].add_space(8.0);
+
+                cols[1].strong("Top sets");
+                let sets: Vec<_> = stats
+                    .sets
+                    .iter()
+                    .map(|(set, n)| (set.clone(), *n as f32, accent))
+                    .collect();
+                chart::bars(&mut cols[1], "sets", &sets, count);
+            });
+
+            ui.add_space(8.0);
+            ui.columns(2, |cols| {
+                cols[0].strong("Most valuable");
+                egui::Grid::new("valuable")
+                    .striped(true)
+                    .show(&mut cols[0], |ui| {
+                        for entry in &stats.valuable {
+                            if ui.link(&entry.name).clicked() {
+                                detail = Some(entry.clone());
                             }
-                            "Color Id" => {
-                                ui.horizontal(|ui| {
-                                    ui.style_mut().spacing.item_spacing = egui::vec2(1.0, 1.0);
-                                    for c in field.chars() {
-                                        ui.label(color_ident(c));
+                            ui.colored_label(
+                                color_code_user(&entry.owner),
+                                entry.owner.to_string(),
+                            );
+                            ui.label(entry.price.map_or(String::new(), |p| format!("{p:.2}€")));
+                            ui.end_row();
+                        }
+                    });
+
+                cols[1].strong("Most duplicated");
+                egui::Grid::new("duplicated")
+                    .striped(true)
+                    .show(&mut cols[1], |ui| {
+                        for (name, n) in &stats.duplicated {
+                            ui.label(name);
+                            ui.label(format!("{n}x"));
+                            ui.end_row();
+                        }
+                    });
+            });
+        });
+
+        if detail.is_some() {
+            self.detail = detail;
+        }
+    }
+
+    fn mk_matches(&mut self, ui: &mut egui::Ui) {
+        if self.wants.lists().is_empty() {
+            ui.weak("No one has a wantlist yet, they go in assets/wants/{user}.txt");
+            return;
+        }
+
+        let mut search = None;
+        ui.horizontal(|ui| {
+            ui.label("Search the wants of:");
+            for (user, list) in self.wants.lists() {
+                if ui
+                    .button(
+                        egui::RichText::new(user.to_string())
+                            .color(color_code_user(&(*user).into())),
+                    )
+                    .clicked()
+                {
+                    search = Some(Search::Wantlist {
+                        list: list.clone(),
+                        owners: Owners::excluding(Some(*user)),
+                    });
+                }
+            }
+        });
+
+        let mut detail = None;
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for Match {
+                owner,
+                wanter,
+                cards,
+            } in &self.matches
+            {
+                let cards: Vec<&Entry> = cards
+                    .iter()
+                    .map(|&i| &self.data[i])
+                    .filter(|entry| !self.only_free || self.usage.free(entry) > 0)
+                    .collect();
+                if cards.is_empty() {
+                    continue;
+                }
+
+                egui::CollapsingHeader::new(format!(
+                    "{owner} has {} cards {wanter} wants",
+                    cards.len()
+                ))
+                .id_salt((owner, wanter))
+                .show(ui, |ui| {
+                    egui::Grid::new((owner, wanter))
+                        .striped(true)
+                        .show(ui, |ui| {
+                            for entry in cards {
+                                if ui.link(&entry.name).clicked() {
+                                    detail = Some(entry.clone());
+                                }
+                                ui.label(&entry.set);
+                                ui.label(format!("{}x", entry.quantity));
+                                ui.label(format!("{} free", self.usage.free(entry)));
+                                ui.label(entry.price.map_or(String::new(), |p| format!("{p:.2}€")));
+                                ui.end_row();
+                            }
+                        });
+                });
+            }
+        });
+
+        if detail.is_some() {
+            self.detail = detail;
+        }
+        if let Some(search) = search {
+            self.search = search;
+            self.view = View::Cards;
+        }
+    }
+
+    fn mk_trades(&mut self, ui: &mut egui::Ui) {
+        if self.trades.is_empty() {
+            ui.weak("No two members have something the other wants");
+            return;
+        }
+
+        if ui
+            .button("Copy as text")
+            .on_hover_text("Copy every suggested trade, e.g. for the group chat")
+            .clicked()
+        {
+            ui.ctx().copy_text(trades::export(&self.data, &self.trades));
+        }
+
+        let mut detail = None;
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for (n, trade) in self.trades.iter().enumerate() {
+                egui::CollapsingHeader::new(format!(
+                    "{} ({})",
+                    trade.title(),
+                    trade.balance_text()
+                ))
+                .id_salt(("trade", n))
+                .show(ui, |ui| {
+                    for leg in &trade.legs {
+                        ui.horizontal(|ui| {
+                            ui.colored_label(
+                                color_code_user(&leg.from.into()),
+                                leg.from.to_string(),
+                            );
+                            ui.label("gives");
+                            ui.colored_label(color_code_user(&leg.to.into()), leg.to.to_string());
+                            ui.weak(leg.value_text());
+                        });
+                        egui::Grid::new(("leg", n, leg.from))
+                            .striped(true)
+                            .show(ui, |ui| {
+                                for &i in &leg.cards {
+                                    let entry = &self.data[i];
+                                    if ui.link(&entry.name).clicked() {
+                                        detail = Some(entry.clone());
+                                    }
+                                    ui.label(&entry.set);
+                                    ui.label(
+                                        entry.price.map_or(String::new(), |p| format!("{p:.2}€")),
+                                    );
+                                    ui.end_row();
+                                }
+                            });
+                    }
+                });
+            }
+        });
+
+        if detail.is_some() {
+            self.detail = detail;
+        }
+    }
+
+    fn guests(&self) -> Vec<Owner> {
+        let mut guests: Vec<Owner> = self
+            .data
+            .iter()
+            .filter(|entry| matches!(entry.owner, Owner::Guest(_)))
+            .map(|entry| entry.owner.clone())
+            .collect();
+        guests.sort();
+        guests.dedup();
+        guests
+    }
+
+    fn pick_file(&self) {
+        let sender = self.files.0.clone();
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(path) = rfd::FileDialog::new()
+            .add_filter("CSV", &["csv"])
+            .pick_file()
+        {
+            match std::fs::read(&path) {
+                Ok(bytes) => {
+                    let name = path.file_name().unwrap_or_default().to_string_lossy();
+                    let _ = sender.send((name.into_owned(), bytes));
+                }
+                Err(e) => log::error!("Failed reading {}: {e}", path.display()),
+            }
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        wasm_bindgen_futures::spawn_local(async move {
+            if let Some(file) = rfd::AsyncFileDialog::new()
+                .add_filter("CSV", &["csv"])
+                .pick_file()
+                .await
+            {
+                let _ = sender.send((file.file_name(), file.read().await));
+            }
+        });
+    }
+
+    fn receive_files(&mut self, ctx: &egui::Context) {
+        for file in ctx.input(|i| i.raw.dropped_files.clone()) {
+            let name = file
+                .path
+                .as_ref()
+                .and_then(|path| path.file_name())
+                .map_or(file.name.clone(), |name| {
+                    name.to_string_lossy().into_owned()
+                });
+            let bytes = match (file.bytes, &file.path) {
+                (Some(bytes), _) => Ok(bytes.to_vec()),
+                (None, Some(path)) => std::fs::read(path),
+                (None, None) => continue,
+            };
+            match bytes {
+                Ok(bytes) => {
+                    let _ = self.files.0.send((name, bytes));
+                }
+                Err(e) => log::error!("Failed reading dropped file {name}: {e}"),
+            }
+        }
+
+        if let Ok((name, bytes)) = self.files.1.try_recv() {
+            self.import = Some(Import::new(&name, bytes));
+        }
+
+        if ctx.input(|i| !i.raw.hovered_files.is_empty()) {
+            let screen = ctx.screen_rect();
+            let painter = ctx.layer_painter(egui::LayerId::new(
+                egui::Order::Foreground,
+                egui::Id::new("file_drop"),
+            ));
+            painter.rect_filled(screen, 0.0, Color32::from_black_alpha(192));
+            painter.text(
+                screen.center(),
+                egui::Align2::CENTER_CENTER,
+                "Drop a collection CSV to import it",
+                egui::FontId::proportional(24.0),
+                Color32::WHITE,
+            );
+        }
+    }
+
+    fn show_import(&mut self, ctx: &egui::Context) {
+        let Some(import) = &mut self.import else {
+            return;
+        };
+
+        let mut open = true;
+        let mut done = false;
+        egui::Window::new("Import collection")
+            .open(&mut open)
+            .resizable(false)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                if import.headers.is_empty() {
+                    ui.colored_label(
+                        ui.visuals().error_fg_color,
+                        import.error.as_deref().unwrap_or_default(),
+                    );
+                    return;
+                }
+
+                ui.horizontal(|ui| {
+                    ui.label("Guest name:");
+                    ui.text_edit_singleline(&mut import.name);
+                });
+                match import.format {
+                    Some(format) => ui.label(format!("Detected a {format} export")),
+                    None => ui.label("Unknown layout, map the columns below"),
+                };
+
+                ui.separator();
+
+                let headers = &import.headers;
+                let columns = &mut import.columns;
+                egui::Grid::new("columns").num_columns(2).show(ui, |ui| {
+                    ui.label("Name");
+                    egui::ComboBox::from_id_salt("column_name")
+                        .selected_text(columns.name.as_str())
+                        .show_ui(ui, |ui| {
+                            for header in headers {
+                                ui.selectable_value(&mut columns.name, header.clone(), header);
+                            }
+                        });
+                    ui.end_row();
+
+                    for (label, column) in [
+                        ("Quantity", &mut columns.quantity),
+                        ("Set Code", &mut columns.set),
+                        ("Scryfall ID", &mut columns.scryfall),
+                        ("Price", &mut columns.price),
+                        ("Type", &mut columns.ty),
+                        ("Color Identity", &mut columns.color),
+                    ] {
+                        ui.label(label);
+                        egui::ComboBox::from_id_salt(label)
+                            .selected_text(column.as_deref().unwrap_or("-"))
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(column, None, "-");
+                                for header in headers {
+                                    ui.selectable_value(column, Some(header.clone()), header);
+                                }
+                            });
+                        ui.end_row();
+                    }
+                });
+
+                if let Some(error) = &import.error {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                }
+
+                if ui.button("Import").clicked() {
+                    let owner = import.owner();
+                    if self.data.iter().any(|entry| entry.owner == owner) {
+                        import.error = Some(format!("{owner} is already imported"));
+                    } else {
+                        match import.read() {
+                            Ok(parsed) if parsed.entries.is_empty() => {
+                                import.error = Some(format!(
+                                    "No readable rows, {} skipped",
+                                    parsed.skipped.len()
+                                ));
+                            }
+                            Ok(parsed) => {
+                                merge(&mut self.data, &mut self.issues, owner, Ok(parsed));
+                                done = true;
+                            }
+                            Err(e) => import.error = Some(e.to_string()),
+                        }
+                    }
+                }
+            });
+
+        if done {
+            self.reindex();
+        }
+        if !open || done {
+            self.import = None;
+        }
+    }
+
+    fn show_saved(&mut self, ctx: &egui::Context) {
+        let Some(window) = &mut self.saved_window else {
+            return;
+        };
+
+        let mut open = true;
+        egui::Window::new("Saved searches")
+            .open(&mut open)
+            .resizable(false)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.text_edit_singleline(&mut window.name)
+                        .on_hover_text("Saving under an existing name replaces it");
+                    let name = window.name.trim();
+                    if ui
+                        .add_enabled(!name.is_empty(), egui::Button::new("Save current search"))
+                        .clicked()
+                    {
+                        saved::save(
+                            &mut self.saved,
+                            SavedSearch {
+                                name: name.to_string(),
+                                search: self.search.clone(),
+                                dedup: self.dedup,
+                            },
+                        );
+                        window.name.clear();
+                    }
+                });
+
+                ui.separator();
+                let mut remove = None;
+                for (i, saved) in self.saved.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        if ui.small_button("✖").on_hover_text("Delete").clicked() {
+                            remove = Some(i);
+                        }
+                        ui.label(&saved.name);
+                    });
+                }
+                if let Some(i) = remove {
+                    self.saved.remove(i);
+                }
+                if self.saved.is_empty() {
+                    ui.weak("No saved searches yet");
+                }
+
+                ui.separator();
+                if ui
+                    .add_enabled(!self.saved.is_empty(), egui::Button::new("Copy as JSON"))
+                    .on_hover_text("Share your saved searches with the pod")
+                    .clicked()
+                {
+                    ctx.copy_text(saved::export(&self.saved));
+                }
+                ui.label("Import searches someone shared:");
+                ui.add(
+                    egui::TextEdit::multiline(&mut window.json)
+                        .hint_text("Paste JSON here")
+                        .desired_rows(4),
+                );
+                if ui.button("Import").clicked() {
+                    match saved::import(&mut self.saved, &window.json) {
+                        Ok(_) => {
+                            window.json.clear();
+                            window.error = None;
+                        }
+                        Err(e) => window.error = Some(format!("Not a list of saved searches: {e}")),
+                    }
+                }
+                if let Some(error) = &window.error {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                }
+            });
+
+        if !open {
+            self.saved_window = None;
+        }
+    }
+
+    fn show_plan(&mut self, ctx: &egui::Context) {
+        let Some(plan) = &self.plan else {
+            return;
+        };
+
+        let mut open = true;
+        egui::Window::new("Borrowing plan")
+            .open(&mut open)
+            .resizable(false)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                if plan.pulls.is_empty() {
+                    ui.weak("No one has any of these cards free");
+                } else {
+                    ui.label(format!(
+                        "Borrow from {} owners, {} worth of cards",
+                        plan.pulls.len(),
+                        plan.value_text()
+                    ));
+                }
+
+                egui::ScrollArea::vertical()
+                    .max_height(400.0)
+                    .show(ui, |ui| {
+                        for pull in &plan.pulls {
+                            ui.separator();
+                            ui.colored_label(color_code_user(&pull.owner), pull.owner.to_string());
+                            egui::Grid::new(("pull", &pull.owner))
+                                .striped(true)
+                                .show(ui, |ui| {
+                                    for card in &pull.cards {
+                                        ui.label(format!("{}x", card.quantity));
+                                        ui.label(&card.name);
+                                        ui.weak(card.sets.join(", "));
+                                        ui.label(card.value_text());
+                                        ui.end_row();
                                     }
                                 });
+                        }
+
+                        if !plan.missing.is_empty() {
+                            ui.separator();
+                            ui.colored_label(ui.visuals().warn_fg_color, "No one has free:");
+                            for (name, quantity) in &plan.missing {
+                                ui.label(format!("{quantity}x {name}"));
                             }
-                            "Scryfall" => {
-                                ui.hyperlink_to(
-                                    field.clone(),
-                                    format!(
-                                        "https://scryfall.com/search?q=scryfall_id%3A{}",
-                                        field.clone()
-                                    ),
-                                )
-                                .on_hover_ui(|ui| {
-                                    ui.add(
-                                        egui::Image::new(format!(
-                                            "https://cards.scryfall.io/png/front/{}/{}/{}.png",
-                                            field.as_bytes()[0] as char,
-                                            field.as_bytes()[1] as char,
-                                            field.clone()
-                                        ))
-                                        .fit_to_exact_size(egui::vec2(292.0, 408.0)),
+                        }
+                    });
+
+                ui.separator();
+                if ui
+                    .button("Copy as text")
+                    .on_hover_text("Copy the pull lists, one per owner")
+                    .clicked()
+                {
+                    ctx.copy_text(plan.to_text());
+                }
+            });
+
+        if !open {
+            self.plan = None;
+        }
+    }
+
+    fn show_cart(&mut self, ctx: &egui::Context) {
+        let Some(window) = &mut self.cart_window else {
+            return;
+        };
+
+        let mut open = true;
+        let mut remove = None;
+        egui::Window::new("Pull lists")
+            .open(&mut open)
+            .resizable(false)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                if self.cart.is_empty() {
+                    ui.weak("Tick cards in the table to add them here");
+                    return;
+                }
+
+                ui.horizontal(|ui| {
+                    ui.label("Borrower:");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut window.borrower)
+                            .hint_text("Who the cards are for"),
+                    );
+                });
+
+                let mut copy = None;
+                egui::ScrollArea::vertical()
+                    .max_height(400.0)
+                    .show(ui, |ui| {
+                        let mut owner = None;
+                        for picked in &mut self.cart.picked {
+                            let card = &picked.card;
+                            if owner != Some(&card.owner) {
+                                owner = Some(&card.owner);
+                                ui.separator();
+                                ui.horizontal(|ui| {
+                                    ui.colored_label(
+                                        color_code_user(&card.owner),
+                                        card.owner.to_string(),
                                     );
+                                    if ui
+                                        .small_button("Copy message")
+                                        .on_hover_text("Copy this owner's pull list for the chat")
+                                        .clicked()
+                                    {
+                                        copy = Some(card.owner.clone());
+                                    }
                                 });
                             }
-                            _ => {
-                                ui.label(field);
-                            }
-                        });
+
+                            ui.horizontal(|ui| {
+                                if ui.small_button("✖").on_hover_text("Remove").clicked() {
+                                    remove = Some(card.clone());
+                                }
+                                ui.add(
+                                    egui::DragValue::new(&mut picked.quantity)
+                                        .range(1..=picked.owned)
+                                        .suffix("x"),
+                                );
+                                ui.label(&card.name);
+                                ui.weak(&card.set);
+                                ui.weak(&card.scryfall);
+                            });
+                        }
+                    });
+                if let Some(owner) = copy {
+                    ctx.copy_text(self.cart.message(&owner, &window.borrower));
+                }
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    if ui
+                        .button("Save printable page…")
+                        .on_hover_text("A checklist per owner to tick off while pulling cards")
+                        .clicked()
+                    {
+                        save_html("pull-lists.html", self.cart.html(&window.borrower));
                     }
-                })
+                    if ui.button("Clear").clicked() {
+                        self.cart.clear();
+                    }
+                });
             });
+
+        if let Some(card) = remove {
+            self.cart.remove(&card);
+        }
+        if !open {
+            self.cart_window = None;
+        }
+    }
+
+    fn show_detail(&mut self, ctx: &egui::Context) {
+        let Some(entry) = &self.detail else {
+            return;
+        };
+
+        let mut open = true;
+        egui::Window::new(&entry.name)
+            .open(&mut open)
+            .resizable(false)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    if let Some(image) = card_image(&entry.scryfall) {
+                        ui.add(egui::Image::new(image).fit_to_exact_size(egui::vec2(146.0, 204.0)));
+                    }
+                    ui.vertical(|ui| {
+                        ui.colored_label(color_code_user(&entry.owner), entry.owner.to_string());
+                        ui.label(&entry.ty);
+                        ui.label(format!("Set: {}", entry.set));
+                        if let Some(price) = entry.price {
+                            ui.label(format!("Price: {price:.2}€"));
+                        }
+                    });
+                });
+
+                ui.separator();
+                ui.strong("Price history");
+
+                let points: Vec<(f32, f32)> = self
+                    .history
+                    .series(&entry.scryfall)
+                    .iter()
+                    .map(|point| (point.day as f32, point.price))
+                    .collect();
+                chart::steps(ui, &points, egui::vec2(300.0, 120.0), |day, price| {
+                    format!("{}: {price:.2}€", archidekt::date(day as u32))
+                });
+            });
+
+        if !open {
+            self.detail = None;
+        }
     }
 }
 
@@ -141,45 +1618,204 @@ impl eframe::App for App {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.horizontal(|ui| {
-                ui.selectable_value(&mut self.search, Search::single(), "Simple");
-                ui.selectable_value(&mut self.search, Search::wantlist(), "Wantlist");
+                let wantlist = matches!(self.search, Search::Wantlist { .. });
+                if ui.selectable_label(!wantlist, "Simple").clicked() && wantlist {
+                    self.search = Search::single();
+                }
+                if ui.selectable_label(wantlist, "Wantlist").clicked() && !wantlist {
+                    self.search = Search::wantlist(self.me);
+                }
+                #[cfg(target_arch = "wasm32")]
+                if ui
+                    .button("🔗 Copy link")
+                    .on_hover_text("Copy a link to this search")
+                    .clicked()
+                {
+                    ctx.copy_text(crate::share::link(&crate::share::to_fragment(
+                        &self.search,
+                        self.dedup,
+                    )));
+                }
+                egui::ComboBox::from_id_salt("saved")
+                    .selected_text("⭐ Saved")
+                    .show_ui(ui, |ui| {
+                        for saved in &self.saved {
+                            if ui.button(&saved.name).clicked() {
+                                self.search = saved.search.clone();
+                                self.dedup = saved.dedup;
+                            }
+                        }
+                        ui.separator();
+                        if ui.button("Manage…").clicked() {
+                            self.saved_window = Some(SavedWindow::default());
+                        }
+                    });
+                ui.separator();
+                ui.checkbox(&mut self.dedup, "Group printings?")
+                    .on_hover_text("One row per card, expand it to see every printing");
+                ui.add_enabled(
+                    self.dedup,
+                    egui::Checkbox::new(&mut self.across_owners, "…across owners"),
+                )
+                .on_hover_text("One row per card for the whole pod rather than per owner");
+                ui.checkbox(&mut self.only_free, "Only free copies")
+                    .on_hover_text("Hide cards whose every copy is in one of their owner's decks");
+                ui.add_enabled(
+                    self.me.is_some(),
+                    egui::Checkbox::new(&mut self.exclude_mine, "Exclude my cards"),
+                )
+                .on_disabled_hover_text("Pick who you are first");
+                ui.menu_button("Columns", |ui| {
+                    // The table needs at least one column
+                    let last = self.columns.visible().count() == 1;
+                    for column in self.columns.all_mut() {
+                        ui.add_enabled(
+                            !(last && column.visible),
+                            egui::Checkbox::new(&mut column.visible, column.kind.label()),
+                        );
+                    }
+                    ui.separator();
+                    if ui.button("Reset").clicked() {
+                        self.columns = Columns::default();
+                    }
+                })
+                .response
+                .on_hover_text("Drag a column's header to move it, or its edge to resize it");
                 ui.separator();
-                ui.checkbox(&mut self.dedup, "Group printings?");
+                ui.selectable_value(&mut self.view, View::Cards, "Cards");
+                ui.selectable_value(&mut self.view, View::Movers, "Price movers");
+                ui.selectable_value(&mut self.view, View::Stats, "Stats");
+                ui.selectable_value(&mut self.view, View::Matches, "Wants")
+                    .on_hover_text("Cards someone has that someone else wants");
+                ui.selectable_value(&mut self.view, View::Trades, "Trades")
+                    .on_hover_text("Members who each have something the other wants");
+                ui.separator();
+                ui.label("I am:");
+                egui::ComboBox::from_id_salt("me")
+                    .selected_text(self.me.map_or(String::new(), |me| me.to_string()))
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut self.me, None, "");
+                        for user in User::VARIANTS {
+                            ui.selectable_value(&mut self.me, Some(*user), user.to_string());
+                        }
+                    })
+                    .response
+                    .on_hover_text("Remembered on this device");
+                ui.separator();
+                if ui.button("Import CSV…").clicked() {
+                    self.pick_file();
+                }
+                if ui
+                    .button(format!("🛒 Pull lists ({})", self.cart.len()))
+                    .on_hover_text("The cards you ticked, grouped by owner")
+                    .clicked()
+                {
+                    self.cart_window = Some(CartWindow {
+                        borrower: self.me.map_or(String::new(), |me| me.to_string()),
+                    });
+                }
             });
 
+            if let Some(note) = self.freshness.describe() {
+                ui.weak(note);
+            }
+
+            if !self.issues.is_empty() {
+                let mut dismiss = false;
+                egui::Frame::group(ui.style()).show(ui, |ui| {
+                    for (owner, issue) in &self.issues {
+                        ui.horizontal(|ui| {
+                            ui.colored_label(color_code_user(owner), format!("{owner}:"));
+                            match issue {
+                                LoadIssue::Failed(error) => {
+                                    ui.colored_label(ui.visuals().error_fg_color, error);
+                                }
+                                LoadIssue::Skipped(rows) => {
+                                    ui.colored_label(
+                                        ui.visuals().warn_fg_color,
+                                        format!("{} rows skipped", rows.len()),
+                                    )
+                                    .on_hover_ui(|ui| {
+                                        for row in rows.iter().take(20) {
+                                            ui.label(row.to_string());
+                                        }
+                                        if rows.len() > 20 {
+                                            ui.label(format!("…and {} more", rows.len() - 20));
+                                        }
+                                    });
+                                }
+                                LoadIssue::NoDecks(error) => {
+                                    ui.colored_label(
+                                        ui.visuals().warn_fg_color,
+                                        "decks unavailable, every copy counts as free",
+                                    )
+                                    .on_hover_text(error);
+                                }
+                            }
+                        });
+                    }
+                    dismiss = ui.small_button("Dismiss").clicked();
+                });
+                if dismiss {
+                    self.issues.clear();
+                }
+            }
+
+            let guests = self.guests();
+            if !guests.is_empty() {
+                ui.horizontal(|ui| {
+                    ui.label("Guests:");
+                    for guest in &guests {
+                        if ui
+                            .button(format!("{guest} ✖"))
+                            .on_hover_text("Remove this collection")
+                            .clicked()
+                        {
+                            self.data.retain(|entry| entry.owner != *guest);
+                            self.issues.retain(|(owner, _)| owner != guest);
+                            self.reindex();
+                        }
+                    }
+                });
+            }
+
             match self.search {
                 Search::Single {
-                    ref mut owner,
+                    ref mut owners,
                     ref mut color,
                     ref mut colorless,
+                    ref mut color_match,
+                    ref mut color_count,
                     ref mut name,
                     ref mut ty,
                     ref mut set,
                 } => {
                     ui.horizontal(|ui| {
                         ui.label("Search: ");
-                        egui::TextEdit::singleline(name).show(ui);
+                        egui::TextEdit::singleline(name)
+                            .show(ui)
+                            .response
+                            .on_hover_text("owner:Name and -owner:Name filter owners too");
                     });
 
                     ui.collapsing("Advanced", |ui| {
-                        ui.horizontal(|ui| {
-                            ui.label("Owner:");
-                            egui::ComboBox::from_id_salt("owner")
-                                .selected_text(
-                                    owner.map_or(String::new(), |owner| owner.to_string()),
-                                )
-                                .show_ui(ui, |ui| {
-                                    ui.selectable_value(owner, None, "");
-                                    for user in archidekt::User::VARIANTS {
-                                        ui.selectable_value(owner, Some(*user), user.to_string());
-                                    }
-                                })
-                        });
+                        owner_chips(ui, owners, &guests);
 
                         ui.spacing();
 
                         ui.horizontal(|ui| {
                             ui.label("Color Identity:");
+                            egui::ComboBox::from_id_salt("color_match")
+                                .width(40.0)
+                                .selected_text(color_match.symbol())
+                                .show_ui(ui, |ui| {
+                                    for mode in ColorMatch::ALL {
+                                        ui.selectable_value(color_match, mode, mode.symbol())
+                                            .on_hover_text(mode.description());
+                                    }
+                                })
+                                .response
+                                .on_hover_text(color_match.description());
                             for (i, c) in "WUBRG".chars().enumerate() {
                                 if ui
                                     .add(egui::Button::selectable(color[i], color_ident(c)))
@@ -197,6 +1833,19 @@ impl eframe::App for App {
                                 *colorless = !*colorless;
                                 **color = [false; 5];
                             }
+
+                            if *colorless || color.iter().any(|c| *c) {
+                                ui.weak(color.name());
+                            }
+                        });
+
+                        ui.spacing();
+
+                        ui.horizontal(|ui| {
+                            ui.label("Colors:");
+                            for count in ColorCount::ALL {
+                                ui.selectable_value(color_count, count, count.label());
+                            }
                         });
 
                         ui.spacing();
@@ -214,7 +1863,10 @@ impl eframe::App for App {
                         });
                     });
                 }
-                Search::Wantlist(ref mut list, ref mut owner) => {
+                Search::Wantlist {
+                    ref mut list,
+                    ref mut owners,
+                } => {
                     ui.label("Wantlist");
                     egui::ScrollArea::vertical()
                         .id_salt("wantlist")
@@ -225,23 +1877,105 @@ impl eframe::App for App {
 
                     ui.spacing();
 
+                    let import = &mut self.deck_import;
                     ui.horizontal(|ui| {
-                        ui.label("Owner:");
-                        egui::ComboBox::from_id_salt("owner")
-                            .selected_text(owner.map_or(String::new(), |owner| owner.to_string()))
-                            .show_ui(ui, |ui| {
-                                ui.selectable_value(owner, None, "");
-                                for user in archidekt::User::VARIANTS {
-                                    ui.selectable_value(owner, Some(*user), user.to_string());
-                                }
+                        ui.label("Deck:");
+                        ui.add(
+                            egui::TextEdit::singleline(&mut import.input)
+                                .hint_text("archidekt.com/decks/… or ID"),
+                        );
+                        ui.checkbox(&mut import.boards, "Maybe- and sideboard");
+                        if ui
+                            .add_enabled(
+                                CAN_IMPORT_DECKS && !import.is_pending(),
+                                egui::Button::new("Import deck"),
+                            )
+                            .on_hover_text("Replace the wantlist with the deck's cards")
+                            .on_disabled_hover_text(if CAN_IMPORT_DECKS {
+                                "Importing…"
+                            } else {
+                                "Archidekt doesn't let websites fetch decks, use the desktop app"
                             })
+                            .clicked()
+                        {
+                            import.start(ui.ctx());
+                        }
+                        if import.is_pending() {
+                            ui.spinner();
+                        }
+                        match &import.status {
+                            Some(Ok(message)) => {
+                                ui.weak(message);
+                            }
+                            Some(Err(error)) => {
+                                ui.colored_label(ui.visuals().error_fg_color, error);
+                            }
+                            None => {}
+                        }
+                    });
+
+                    owner_chips(ui, owners, &guests);
+
+                    ui.horizontal(|ui| {
+                        if ui
+                            .button("Plan borrowing…")
+                            .on_hover_text(
+                                "Find who to borrow the wantlist from, bothering as few people \
+                                 as possible",
+                            )
+                            .clicked()
+                        {
+                            self.plan = Some(Plan::new(list, &self.data, &self.usage, owners));
+                        }
                     });
                 }
             }
 
             ui.separator();
 
-            self.mk_table(ui);
+            if let Some(deck) = self.deck_import.poll() {
+                self.search = Search::Wantlist {
+                    list: deck.wantlist(self.deck_import.boards),
+                    owners: Owners::excluding(deck.member.or(self.me)),
+                };
+            }
+            self.refresh();
+            #[cfg(target_arch = "wasm32")]
+            self.share_search();
+            match self.view {
+                View::Cards => {
+                    // The columns can add up to more than a narrow window fits
+                    egui::ScrollArea::horizontal().show(ui, |ui| self.mk_table(ui));
+                }
+                View::Movers => self.mk_movers(ui),
+                View::Stats => self.mk_stats(ui),
+                View::Matches => self.mk_matches(ui),
+                View::Trades => self.mk_trades(ui),
+            }
         });
+
+        self.show_detail(ctx);
+        self.show_import(ctx);
+        self.receive_files(ctx);
+        self.receive_update(ctx);
+        self.show_saved(ctx);
+        self.show_plan(ctx);
+        self.show_cart(ctx);
+    }
+
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        let persisted = Persisted {
+            search: self.search.clone(),
+            dedup: self.dedup,
+            across_owners: self.across_owners,
+            only_free: self.only_free,
+            view: self.view,
+            movers_days: self.movers_days,
+            me: self.me,
+            exclude_mine: self.exclude_mine,
+            columns: self.columns.clone(),
+        };
+        eframe::set_value(storage, eframe::APP_KEY, &persisted);
+        saved::store(storage, &self.saved);
     }
 }
@@ -0,0 +1,263 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use archidekt::{Entry, Owner};
+
+/// Tells a printing of an owner's card apart from the rest of the collection
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct CardKey {
+    pub owner: Owner,
+    pub name: String,
+    pub set: String,
+    pub scryfall: String,
+}
+
+impl CardKey {
+    pub fn new(entry: &Entry) -> Self {
+        Self {
+            owner: entry.owner.clone(),
+            name: entry.name.clone(),
+            set: entry.set.clone(),
+            scryfall: entry.scryfall.clone(),
+        }
+    }
+
+    fn is(&self, entry: &Entry) -> bool {
+        self.owner == entry.owner
+            && self.name == entry.name
+            && self.set == entry.set
+            && self.scryfall == entry.scryfall
+    }
+}
+
+/// A card picked from the table and how many copies of it to ask for
+#[derive(Debug, Clone)]
+pub struct Picked {
+    pub card: CardKey,
+    pub quantity: u32,
+    /// How many copies the owner has, as of the last `Cart::sync`
+    pub owned: u32,
+}
+
+/// Cards picked to borrow, to be turned into one pull list per owner
+#[derive(Debug, Default)]
+pub struct Cart {
+    /// Sorted by `CardKey`, so each owner's cards are next to each other
+    pub picked: Vec<Picked>,
+}
+
+/// The state of the pull lists window
+#[derive(Debug, Default)]
+pub struct CartWindow {
+    /// Who the cards are for, mentioned in the exports
+    pub borrower: String,
+}
+
+impl Cart {
+    pub fn len(&self) -> usize {
+        self.picked.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.picked.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.picked.clear();
+    }
+
+    pub fn contains(&self, entry: &Entry) -> bool {
+        self.picked.iter().any(|picked| picked.card.is(entry))
+    }
+
+    /// Picks one copy of the entry, or puts it back if it's already picked
+    pub fn toggle(&mut self, entry: &Entry) {
+        match self.picked.iter().position(|picked| picked.card.is(entry)) {
+            Some(i) => {
+                self.picked.remove(i);
+            }
+            None => {
+                let card = CardKey::new(entry);
+                let i = self.picked.partition_point(|picked| picked.card < card);
+                self.picked.insert(
+                    i,
+                    Picked {
+                        card,
+                        quantity: 1,
+                        owned: entry.quantity,
+                    },
+                );
+            }
+        }
+    }
+
+    pub fn remove(&mut self, card: &CardKey) {
+        self.picked.retain(|picked| picked.card != *card);
+    }
+
+    /// Catches up with a changed collection, dropping cards that are gone and asking for no more
+    /// copies than there are
+    pub fn sync(&mut self, data: &[Entry]) {
+        for picked in &mut self.picked {
+            picked.owned = data
+                .iter()
+                .filter(|entry| picked.card.is(entry))
+                .map(|entry| entry.quantity)
+                .sum();
+            picked.quantity = picked.quantity.min(picked.owned);
+        }
+        self.picked.retain(|picked| picked.owned > 0);
+    }
+
+    /// The picked cards grouped by owner
+    pub fn by_owner(&self) -> BTreeMap<&Owner, Vec<&Picked>> {
+        let mut owners: BTreeMap<&Owner, Vec<&Picked>> = BTreeMap::new();
+        for picked in &self.picked {
+            owners.entry(&picked.card.owner).or_default().push(picked);
+        }
+        owners
+    }
+
+    /// A message asking `owner` to bring their picked cards, signed by `borrower` if given
+    pub fn message(&self, owner: &Owner, borrower: &str) -> String {
+        let mut text = match borrower.trim() {
+            "" => format!("Hi {owner}, could you bring these?\n"),
+            borrower => format!("Hi {owner}, could you bring these for {borrower}?\n"),
+        };
+        for picked in self.by_owner().remove(owner).unwrap_or_default() {
+            let card = &picked.card;
+            let _ = writeln!(
+                text,
+                "- {}x {} ({}) {}",
+                picked.quantity, card.name, card.set, card.scryfall
+            );
+        }
+        text
+    }
+
+    /// A printable page with a checklist per owner to tick off while pulling cards
+    pub fn html(&self, borrower: &str) -> String {
+        let borrower = borrower.trim();
+        let title = match borrower {
+            "" => "Pull lists".to_string(),
+            borrower => format!("Pull lists for {}", escape(borrower)),
+        };
+
+        let mut html = format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n\
+             <style>\n\
+             body {{ font-family: sans-serif; }}\n\
+             section {{ break-inside: avoid; margin-bottom: 2em; }}\n\
+             table {{ border-collapse: collapse; }}\n\
+             td, th {{ border-bottom: 1px solid #ccc; padding: 4px 8px; text-align: left; }}\n\
+             .box {{ width: 1em; height: 1em; border: 1px solid #000; }}\n\
+             .id {{ font-family: monospace; font-size: 0.8em; }}\n\
+             </style>\n</head>\n<body>\n<h1>{title}</h1>\n"
+        );
+        for (owner, picked) in self.by_owner() {
+            let _ = write!(
+                html,
+                "<section>\n<h2>{}</h2>\n<table>\n\
+                 <tr><th></th><th>Qty</th><th>Name</th><th>Set</th><th>Scryfall ID</th></tr>\n",
+                escape(&owner.to_string())
+            );
+            for picked in picked {
+                let card = &picked.card;
+                let _ = writeln!(
+                    html,
+                    "<tr><td><div class=\"box\"></div></td><td>{}</td><td>{}</td><td>{}</td>\
+                     <td class=\"id\">{}</td></tr>",
+                    picked.quantity,
+                    escape(&card.name),
+                    escape(&card.set),
+                    escape(&card.scryfall)
+                );
+            }
+            html.push_str("</table>\n</section>\n");
+        }
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use archidekt::{ColorIdent, User};
+
+    fn entry(owner: User, name: &str, quantity: u32) -> Entry {
+        Entry {
+            owner: owner.into(),
+            quantity,
+            color_identity: ColorIdent::new(),
+            name: name.into(),
+            ty: String::new(),
+            set: "cmd".into(),
+            scryfall: format!("{name}-id"),
+            price: None,
+        }
+    }
+
+    #[test]
+    fn escapes_html() {
+        assert_eq!(
+            escape(r#"<b>"Ach! Hans, Run!" & more</b>"#),
+            "&lt;b&gt;&quot;Ach! Hans, Run!&quot; &amp; more&lt;/b&gt;"
+        );
+    }
+
+    #[test]
+    fn messages_list_one_owners_cards() {
+        let mut cart = Cart::default();
+        cart.toggle(&entry(User::Strosel, "Sol Ring", 2));
+        cart.toggle(&entry(User::Strosel, "Mind Stone", 1));
+        cart.toggle(&entry(User::VikinGG, "Arcane Signet", 1));
+        cart.picked[1].quantity = 2;
+
+        let owner = User::Strosel.into();
+        assert_eq!(
+            cart.message(&owner, ""),
+            "Hi Strosel, could you bring these?\n\
+             - 1x Mind Stone (cmd) Mind Stone-id\n\
+             - 2x Sol Ring (cmd) Sol Ring-id\n"
+        );
+        assert!(cart
+            .message(&owner, " VikinGG ")
+            .starts_with("Hi Strosel, could you bring these for VikinGG?\n"));
+    }
+
+    #[test]
+    fn html_has_a_section_per_owner() {
+        let mut cart = Cart::default();
+        cart.toggle(&entry(User::Strosel, "Sol Ring", 1));
+        cart.toggle(&entry(User::VikinGG, "Lim-Dûl's <Vault>", 1));
+
+        let html = cart.html("Me & you");
+        assert!(html.contains("<title>Pull lists for Me &amp; you</title>"));
+        assert_eq!(html.matches("<section>").count(), 2);
+        assert!(html.contains("<h2>Strosel</h2>"));
+        assert!(html.contains("<td>Lim-Dûl's &lt;Vault&gt;</td>"));
+    }
+
+    #[test]
+    fn sync_follows_the_collection() {
+        let mut cart = Cart::default();
+        cart.toggle(&entry(User::Strosel, "Sol Ring", 3));
+        cart.toggle(&entry(User::Strosel, "Mind Stone", 1));
+        cart.picked[1].quantity = 3;
+
+        cart.sync(&[entry(User::Strosel, "Sol Ring", 2)]);
+        assert_eq!(cart.len(), 1);
+        assert_eq!(cart.picked[0].card.name, "Sol Ring");
+        assert_eq!(cart.picked[0].quantity, 2);
+        assert_eq!(cart.picked[0].owned, 2);
+        assert!(cart.contains(&entry(User::Strosel, "Sol Ring", 2)));
+    }
+}
@@ -0,0 +1,120 @@
+use eframe::egui::{self, Color32, Pos2, Rect, Sense, Stroke};
+
+/// Draws a step chart of `(x, y)` points, each value holding until the next point.
+/// Hovering shows the value under the cursor formatted with `label`.
+pub fn steps(
+    ui: &mut egui::Ui,
+    points: &[(f32, f32)],
+    size: egui::Vec2,
+    label: impl Fn(f32, f32) -> String,
+) -> egui::Response {
+    let (rect, response) = ui.allocate_exact_size(size, Sense::hover());
+    let painter = ui.painter_at(rect);
+    let visuals = ui.visuals();
+    painter.rect_filled(rect, 2.0, visuals.extreme_bg_color);
+
+    let (Some(first), Some(last)) = (points.first(), points.last()) else {
+        painter.text(
+            rect.center(),
+            egui::Align2::CENTER_CENTER,
+            "No data",
+            egui::FontId::default(),
+            visuals.weak_text_color(),
+        );
+        return response;
+    };
+
+    let (min, max) = points
+        .iter()
+        .fold((f32::MAX, f32::MIN), |(min, max), &(_, y)| {
+            (min.min(y), max.max(y))
+        });
+    // Pad flat or single point series so they don't collapse onto the frame
+    let (min, max) = if max <= min {
+        (min - 1.0, max + 1.0)
+    } else {
+        (min, max)
+    };
+    let (start, end) = (first.0, last.0.max(first.0 + 1.0));
+
+    let plot = rect.shrink(8.0);
+    let to_screen = |x: f32, y: f32| {
+        Pos2::new(
+            egui::remap(x, start..=end, plot.x_range()),
+            egui::remap(y, min..=max, plot.bottom()..=plot.top()),
+        )
+    };
+
+    let mut line = Vec::with_capacity(points.len() * 2);
+    for pair in points.windows(2) {
+        let ((x0, y0), (x1, _)) = (pair[0], pair[1]);
+        line.push(to_screen(x0, y0));
+        line.push(to_screen(x1, y0));
+    }
+    line.push(to_screen(last.0, last.1));
+    line.push(to_screen(end, last.1));
+
+    let stroke = Stroke::new(1.5, visuals.selection.bg_fill);
+    painter.add(egui::Shape::line(line, stroke));
+
+    let text_color = visuals.weak_text_color();
+    let font = egui::FontId::monospace(10.0);
+    painter.text(
+        rect.left_top(),
+        egui::Align2::LEFT_TOP,
+        format!("{max:.2}"),
+        font.clone(),
+        text_color,
+    );
+    painter.text(
+        rect.left_bottom(),
+        egui::Align2::LEFT_BOTTOM,
+        format!("{min:.2}"),
+        font,
+        text_color,
+    );
+
+    if let Some(hover) = response.hover_pos() {
+        let x = egui::remap(hover.x, plot.x_range(), start..=end);
+        if let Some(&(px, py)) = points.iter().take_while(|(px, _)| *px <= x).last() {
+            painter.line_segment(
+                [
+                    Pos2::new(hover.x, plot.top()),
+                    Pos2::new(hover.x, plot.bottom()),
+                ],
+                Stroke::new(1.0, text_color),
+            );
+            painter.circle_filled(to_screen(x, py), 3.0, stroke.color);
+            return response.on_hover_text(label(px, py));
+        }
+    }
+
+    response
+}
+
+/// Draws horizontal bars, one row per `(label, value, color)`, scaled to the largest value
+pub fn bars(
+    ui: &mut egui::Ui,
+    id: impl std::hash::Hash,
+    rows: &[(String, f32, Color32)],
+    format: impl Fn(f32) -> String,
+) {
+    let max = rows.iter().map(|(_, value, _)| *value).fold(0.0, f32::max);
+    let width = (ui.available_width() - 200.0).clamp(100.0, 400.0);
+
+    egui::Grid::new(id)
+        .num_columns(3)
+        .spacing([8.0, 2.0])
+        .show(ui, |ui| {
+            for (label, value, color) in rows {
+                ui.label(label);
+                let (rect, _) = ui.allocate_exact_size(egui::vec2(width, 14.0), Sense::hover());
+                let fill = if max > 0.0 { value / max } else { 0.0 };
+                let bar =
+                    Rect::from_min_size(rect.min, egui::vec2(rect.width() * fill, rect.height()));
+                ui.painter().rect_filled(bar, 2.0, *color);
+                ui.label(format(*value));
+                ui.end_row();
+            }
+        });
+}
@@ -1,171 +1,730 @@
-use archidekt::{Collection, ColorIdent, Entry, User};
+use archidekt::{
+    Collection, ColorIdent, Deck, Owner, Parsed, PriceHistory, SkippedRow, User, HISTORY_FILE,
+};
 use strum::VariantArray;
 
+/// How the selected colors are compared to a card's color identity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+pub enum ColorMatch {
+    Exactly,
+    #[default]
+    AtMost,
+    AtLeast,
+    Includes,
+}
+
+impl ColorMatch {
+    pub const ALL: [Self; 4] = [Self::Exactly, Self::AtMost, Self::AtLeast, Self::Includes];
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            ColorMatch::Exactly => "=",
+            ColorMatch::AtMost => "≤",
+            ColorMatch::AtLeast => "≥",
+            ColorMatch::Includes => "any",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            ColorMatch::Exactly => "Exactly these colors",
+            ColorMatch::AtMost => "At most these colors, i.e. fits in a deck of these colors",
+            ColorMatch::AtLeast => "At least these colors",
+            ColorMatch::Includes => "Includes any of these colors",
+        }
+    }
+
+    pub fn test(&self, wanted: &ColorIdent, card: &ColorIdent) -> bool {
+        match self {
+            ColorMatch::Exactly => card == wanted,
+            ColorMatch::AtMost => card.is_subset(wanted),
+            ColorMatch::AtLeast => card.is_superset(wanted),
+            ColorMatch::Includes => card.intersects(wanted),
+        }
+    }
+}
+
+/// How many colors a card's color identity has
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+pub enum ColorCount {
+    #[default]
+    Any,
+    Mono,
+    Two,
+    ThreePlus,
+}
+
+impl ColorCount {
+    pub const ALL: [Self; 4] = [Self::Any, Self::Mono, Self::Two, Self::ThreePlus];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ColorCount::Any => "Any",
+            ColorCount::Mono => "Mono",
+            ColorCount::Two => "Two-color",
+            ColorCount::ThreePlus => "3+ colors",
+        }
+    }
+
+    pub fn test(&self, card: &ColorIdent) -> bool {
+        match self {
+            ColorCount::Any => true,
+            ColorCount::Mono => card.count() == 1,
+            ColorCount::Two => card.count() == 2,
+            ColorCount::ThreePlus => card.count() >= 3,
+        }
+    }
+}
+
+/// Which owners' cards to show, everyone's when both lists are empty
+#[derive(Debug, Clone, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(from = "StoredOwners", into = "StoredOwners")]
+pub struct Owners {
+    /// Only these owners, unless empty
+    pub include: Vec<Owner>,
+    /// Never these owners, e.g. whoever isn't coming tonight
+    pub exclude: Vec<Owner>,
+}
+
+/// How `Owners` is stored, members and guests apart since RON can't tell them apart in an
+/// untagged `Owner`
+#[derive(serde::Serialize, serde::Deserialize, Default)]
+#[serde(default)]
+struct StoredOwners {
+    include: Vec<User>,
+    exclude: Vec<User>,
+    include_guests: Vec<String>,
+    exclude_guests: Vec<String>,
+}
+
+impl From<StoredOwners> for Owners {
+    fn from(stored: StoredOwners) -> Self {
+        let owners = |members: Vec<User>, guests: Vec<String>| {
+            members
+                .into_iter()
+                .map(Owner::Member)
+                .chain(guests.into_iter().map(Owner::Guest))
+                .collect()
+        };
+        Self {
+            include: owners(stored.include, stored.include_guests),
+            exclude: owners(stored.exclude, stored.exclude_guests),
+        }
+    }
+}
+
+impl From<Owners> for StoredOwners {
+    fn from(owners: Owners) -> Self {
+        let split = |owners: Vec<Owner>| {
+            let mut members = Vec::new();
+            let mut guests = Vec::new();
+            for owner in owners {
+                match owner {
+                    Owner::Member(user) => members.push(user),
+                    Owner::Guest(name) => guests.push(name),
+                }
+            }
+            (members, guests)
+        };
+        let (include, include_guests) = split(owners.include);
+        let (exclude, exclude_guests) = split(owners.exclude);
+        Self {
+            include,
+            exclude,
+            include_guests,
+            exclude_guests,
+        }
+    }
+}
+
+impl Owners {
+    /// Everyone but `user`, if any
+    pub fn excluding(user: Option<User>) -> Self {
+        Self {
+            include: Vec::new(),
+            exclude: user.into_iter().map(Owner::Member).collect(),
+        }
+    }
+
+    pub fn is_everyone(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    pub fn test(&self, owner: &Owner) -> bool {
+        (self.include.is_empty() || self.include.contains(owner)) && !self.exclude.contains(owner)
+    }
+
+    /// Moves an owner from neither list to included, then excluded, then back
+    pub fn cycle(&mut self, owner: Owner) {
+        if self.include.contains(&owner) {
+            self.include.retain(|other| *other != owner);
+            self.exclude.push(owner);
+        } else if self.exclude.contains(&owner) {
+            self.exclude.retain(|other| *other != owner);
+        } else {
+            self.include.push(owner);
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(from = "StoredSearch")]
 pub enum Search {
     Single {
+        owners: Owners,
+        color: ColorIdent,
+        colorless: bool,
+        color_match: ColorMatch,
+        color_count: ColorCount,
+        name: String,
+        ty: String,
+        set: String,
+    },
+    Wantlist {
+        list: String,
+        /// Usually leaves out the cards of whoever the list is for
+        owners: Owners,
+    },
+}
+
+/// How `Search` is read, so searches from before the owner filter took several owners keep
+/// their `owner` and `exclude`
+#[derive(serde::Deserialize)]
+enum StoredSearch {
+    Single {
+        #[serde(default)]
+        owners: Owners,
+        #[serde(default)]
         owner: Option<User>,
         color: ColorIdent,
         colorless: bool,
+        #[serde(default)]
+        color_match: ColorMatch,
+        #[serde(default)]
+        color_count: ColorCount,
         name: String,
         ty: String,
         set: String,
     },
-    Wantlist(String, Option<User>),
+    Wantlist {
+        list: String,
+        #[serde(default)]
+        owners: Owners,
+        #[serde(default)]
+        owner: Option<User>,
+        #[serde(default)]
+        exclude: Option<User>,
+    },
+}
+
+impl From<StoredSearch> for Search {
+    fn from(stored: StoredSearch) -> Self {
+        let owners = |owners: Owners, owner: Option<User>, exclude: Option<User>| {
+            if !owners.is_everyone() {
+                return owners;
+            }
+            Owners {
+                include: owner.into_iter().map(Owner::Member).collect(),
+                exclude: exclude.into_iter().map(Owner::Member).collect(),
+            }
+        };
+        match stored {
+            StoredSearch::Single {
+                owners: stored_owners,
+                owner,
+                color,
+                colorless,
+                color_match,
+                color_count,
+                name,
+                ty,
+                set,
+            } => Search::Single {
+                owners: owners(stored_owners, owner, None),
+                color,
+                colorless,
+                color_match,
+                color_count,
+                name,
+                ty,
+                set,
+            },
+            StoredSearch::Wantlist {
+                list,
+                owners: stored_owners,
+                owner,
+                exclude,
+            } => Search::Wantlist {
+                list,
+                owners: owners(stored_owners, owner, exclude),
+            },
+        }
+    }
 }
 
 impl Search {
     pub fn single() -> Self {
         Self::Single {
-            owner: None,
+            owners: Owners::default(),
             color: ColorIdent::new(),
             colorless: false,
+            color_match: ColorMatch::default(),
+            color_count: ColorCount::default(),
             name: String::new(),
             ty: String::new(),
             set: String::new(),
         }
     }
 
-    pub fn wantlist() -> Self {
-        Search::Wantlist(String::new(), None)
+    /// An empty wantlist leaving out `exclude`'s cards, usually whoever is using the app
+    pub fn wantlist(exclude: Option<User>) -> Self {
+        Search::Wantlist {
+            list: String::new(),
+            owners: Owners::excluding(exclude),
+        }
     }
+}
+
+/// Why an owner's collection is missing or incomplete
+#[derive(Debug, Clone)]
+pub enum LoadIssue {
+    Failed(String),
+    Skipped(Vec<SkippedRow>),
+    /// The collection is there but it's unknown which cards are in decks
+    NoDecks(String),
+}
+
+pub type LoadIssues = Vec<(Owner, LoadIssue)>;
+
+/// The pod's collections, decks and wantlists
+#[derive(Debug, Default)]
+pub struct Loaded {
+    pub data: Collection,
+    pub decks: Vec<Deck>,
+    /// Each member's standing wantlist, members without one are left out
+    pub wants: Vec<(User, String)>,
+    pub issues: LoadIssues,
+}
+
+impl Loaded {
+    fn new() -> Self {
+        Self {
+            data: Collection::with_capacity(1000 * User::VARIANTS.len()),
+            ..Default::default()
+        }
+    }
+
+    /// Adds a fetched collection, noting any issues
+    pub fn merge(&mut self, owner: Owner, fetched: anyhow::Result<Parsed>) {
+        merge(&mut self.data, &mut self.issues, owner, fetched);
+    }
+
+    /// Adds a member's fetched decks, noting if they're missing
+    pub fn merge_decks(&mut self, owner: User, fetched: anyhow::Result<Vec<Deck>>) {
+        match fetched {
+            Ok(mut decks) => self.decks.append(&mut decks),
+            Err(e) => {
+                log::warn!("Failed fetching decks of {owner}: {e}");
+                self.issues
+                    .push((owner.into(), LoadIssue::NoDecks(e.to_string())));
+            }
+        }
+    }
+}
+
+/// How current the loaded collections are
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Freshness {
+    /// Freshly loaded from the network
+    #[default]
+    Live,
+    /// Started from the snapshot kept in browser storage, a newer one is being checked for
+    Checking { created: u64 },
+    /// The snapshot from browser storage is all there is
+    Offline { created: u64 },
+}
 
-    pub fn apply(&self, data: &Entry) -> bool {
+impl Freshness {
+    /// A note on where the data came from, `None` when it's live
+    pub fn describe(&self) -> Option<String> {
+        let date = |created: u64| archidekt::date((created / (60 * 60 * 24)) as u32);
         match self {
-            Search::Single {
-                owner,
-                color,
-                colorless,
-                name,
-                ty,
-                set,
-            } => {
-                let match_owner = owner.is_none_or(|owner| owner == data.owner);
-
-                let match_ident = if *colorless || color.iter().any(|c| *c) {
-                    color.contains(&data.color_identity)
-                } else {
-                    true
-                };
-
-                let match_name = data.name.to_lowercase().contains(&name.to_lowercase());
-
-                let searched_types: Vec<_> = ty
-                    .split(|c: char| !c.is_ascii_alphabetic())
-                    .filter(|s| !s.is_empty())
-                    .collect();
-                let match_types = searched_types.iter().all(|t1| {
-                    data.ty
-                        .to_lowercase()
-                        .split(',')
-                        .any(|t2| t2.contains(&t1.to_lowercase()))
-                });
-
-                let match_set = data.set.to_lowercase().contains(&set.to_lowercase());
-
-                [match_owner, match_ident, match_name, match_types, match_set]
-                    .into_iter()
-                    .all(|x| x)
+            Freshness::Live => None,
+            Freshness::Checking { created } => Some(format!(
+                "Checking for newer data, showing data from {}",
+                date(*created)
+            )),
+            Freshness::Offline { created } => {
+                Some(format!("Offline, data from {}", date(*created)))
             }
-            Search::Wantlist(list, owner) => list.to_lowercase().lines().any(|want| {
-                (owner.is_none_or(|owner| owner == data.owner))
-                    && data.name.to_lowercase().contains(want)
-            }),
         }
     }
 }
 
-impl PartialEq for Search {
-    fn eq(&self, other: &Self) -> bool {
-        matches!(
-            (self, other),
-            (Self::Single { .. }, Self::Single { .. })
-                | (Self::Wantlist { .. }, Search::Wantlist { .. })
-        )
+/// What the background check for a newer snapshot found
+pub enum Update {
+    Refreshed {
+        loaded: Loaded,
+        history: PriceHistory,
+    },
+    UpToDate,
+    Offline,
+}
+
+/// Merges a fetched collection into `collections`, noting any issues
+pub fn merge(
+    collections: &mut Collection,
+    issues: &mut LoadIssues,
+    owner: Owner,
+    fetched: anyhow::Result<Parsed>,
+) {
+    match fetched {
+        Ok(Parsed {
+            mut entries,
+            skipped,
+        }) => {
+            collections.append(&mut entries);
+            if !skipped.is_empty() {
+                log::warn!("Skipped {} rows for {owner}", skipped.len());
+                issues.push((owner, LoadIssue::Skipped(skipped)));
+            }
+        }
+        Err(e) => {
+            log::error!("Failed fetching {owner}: {e}");
+            issues.push((owner, LoadIssue::Failed(e.to_string())));
+        }
     }
 }
 
 #[cfg(not(target_arch = "wasm32"))]
-pub fn get_collections() -> anyhow::Result<Collection> {
-    let mut collections = Collection::with_capacity(1000 * User::VARIANTS.len());
+pub fn client() -> archidekt::ArchidektClient {
+    archidekt::ArchidektClient::builder()
+        .user_agent(concat!("mtg-searcher/", env!("CARGO_PKG_VERSION")))
+        .build()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn get_collections() -> anyhow::Result<Loaded> {
+    let client = client();
+    let sources = archidekt::Sources::load(client.clone())?;
+    let mut loaded = Loaded::new();
 
-    for user in User::VARIANTS {
-        collections.append(&mut archidekt::get_collections(user)?);
+    for (user, fetched) in sources.fetch_all(User::VARIANTS) {
+        loaded.merge(user.into(), fetched);
+    }
+    for (user, fetched) in client.all_decks(User::VARIANTS) {
+        loaded.merge_decks(user, fetched.map_err(anyhow::Error::from));
     }
+    loaded.wants = User::VARIANTS
+        .iter()
+        .filter_map(|user| {
+            std::fs::read_to_string(format!("assets/{}/{user}.txt", archidekt::WANTS_DIR))
+                .ok()
+                .map(|wants| (*user, wants))
+        })
+        .collect();
 
-    Ok(collections)
+    Ok(loaded)
 }
 
 #[cfg(target_arch = "wasm32")]
-pub async fn get_collections() -> anyhow::Result<Collection> {
-    let mut collections = Collection::with_capacity(1000 * User::VARIANTS.len());
-
-    for user in User::VARIANTS {
-        let req = ehttp::Request::get(format!("assets/{user}.csv"));
-        let resp = ehttp::fetch_async(req).await.map_err(anyhow::Error::msg)?;
-        let data = resp
-            .text()
-            .ok_or_else(|| anyhow::Error::msg("Empty CSV body"))?;
-
-        let mut reader = csv::Reader::from_reader(std::io::Cursor::new(data));
-
-        let mut col = reader
-            .deserialize::<Entry>()
-            .collect::<Result<Vec<Entry>, csv::Error>>()?;
-
-        collections.append(&mut col);
-    }
-
-    Ok(collections)
-}
-
-//TODO move dedup to own module
-pub struct CardDeduper<'a, I>
-where
-    I: Iterator<Item = &'a Entry>,
-{
-    on: bool,
-    iter: std::iter::Peekable<I>,
-}
-
-impl<'a, I> Iterator for CardDeduper<'a, I>
-where
-    I: Iterator<Item = &'a Entry>,
-{
-    type Item = Entry;
-    fn next(&mut self) -> Option<Self::Item> {
-        if !self.on {
-            return self.iter.next().cloned();
-        }
-
-        match self.iter.next().cloned() {
-            Some(mut head) => {
-                while let Some(next) = self
-                    .iter
-                    .next_if(|next| next.name == head.name && next.owner == head.owner)
-                {
-                    head.quantity += next.quantity;
-                    if next.set != head.set {
-                        head.set.clear();
-                        head.price = -1.0;
-                    }
-                }
-                Some(head)
+async fn get_collection(user: User) -> anyhow::Result<Parsed> {
+    let req = ehttp::Request::get(format!("assets/{user}.csv"));
+    let resp = ehttp::fetch_async(req).await.map_err(anyhow::Error::msg)?;
+    if !resp.ok {
+        anyhow::bail!("No snapshot of the collection (HTTP {})", resp.status);
+    }
+    let data = resp
+        .text()
+        .ok_or_else(|| anyhow::Error::msg("Empty CSV body"))?;
+
+    let mut reader = csv::Reader::from_reader(std::io::Cursor::new(data));
+
+    Ok(Parsed::from_rows(reader.deserialize::<archidekt::Entry>()))
+}
+
+/// Fetches a file next to the app, keeping a copy in browser storage for offline use
+#[cfg(target_arch = "wasm32")]
+async fn fetch_stored(path: &str) -> anyhow::Result<Vec<u8>> {
+    let req = ehttp::Request::get(path);
+    let resp = ehttp::fetch_async(req).await.map_err(anyhow::Error::msg)?;
+    if !resp.ok {
+        anyhow::bail!("HTTP {} fetching {path}", resp.status);
+    }
+
+    if let Err(e) = crate::storage::store(path, resp.bytes.clone()).await {
+        log::warn!("Failed storing {path} for offline use: {e}");
+    }
+    Ok(resp.bytes)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn bundle_path() -> String {
+    format!("assets/{}", archidekt::BUNDLE_FILE)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn unbundle(bundle: archidekt::Bundle) -> Loaded {
+    let mut loaded = Loaded::new();
+
+    for (user, fetched, decks, wants) in bundle.into_parsed() {
+        loaded.merge(user.into(), fetched);
+        loaded.merge_decks(user, decks);
+        if !wants.is_empty() {
+            loaded.wants.push((user, wants));
+        }
+    }
+
+    loaded
+}
+
+/// The bundle kept from the last visit, unless it's missing or from an incompatible version
+#[cfg(target_arch = "wasm32")]
+async fn stored_bundle() -> Option<archidekt::Bundle> {
+    match crate::storage::load(&bundle_path()).await {
+        Ok(Some(bytes)) => archidekt::Bundle::from_bytes(&bytes)
+            .inspect_err(|e| log::warn!("Ignoring stored collections: {e}"))
+            .ok(),
+        Ok(None) => None,
+        Err(e) => {
+            log::warn!("Failed reading stored collections: {e}");
+            None
+        }
+    }
+}
+
+/// Starts from the copy in browser storage when there is one, otherwise fetches the bundle,
+/// falling back to one CSV per member
+#[cfg(target_arch = "wasm32")]
+pub async fn get_collections() -> anyhow::Result<(Loaded, Freshness)> {
+    if let Some(bundle) = stored_bundle().await {
+        let created = bundle.created;
+        return Ok((unbundle(bundle), Freshness::Checking { created }));
+    }
+
+    let fetched = fetch_stored(&bundle_path())
+        .await
+        .and_then(|bytes| archidekt::Bundle::from_bytes(&bytes));
+    match fetched {
+        Ok(bundle) => Ok((unbundle(bundle), Freshness::Live)),
+        Err(e) => {
+            log::warn!("Falling back to CSV snapshots: {e}");
+            let mut loaded = Loaded::new();
+            for user in User::VARIANTS {
+                loaded.merge((*user).into(), get_collection(*user).await);
             }
-            None => None,
+            Ok((loaded, Freshness::Live))
         }
     }
 }
 
-pub trait CardDeduperExt<'a>
-where
-    Self: Sized + Iterator<Item = &'a Entry>,
-{
-    fn dedup_cards(self, on: bool) -> CardDeduper<'a, Self> {
-        CardDeduper {
-            on,
-            iter: self.peekable(),
+/// Fetches the bundle to see if it's newer than the snapshot from storage created at `created`
+#[cfg(target_arch = "wasm32")]
+pub async fn check_for_update(created: u64) -> Update {
+    let fetched = fetch_stored(&bundle_path())
+        .await
+        .and_then(|bytes| archidekt::Bundle::from_bytes(&bytes));
+    match fetched {
+        Ok(bundle) if bundle.created > created => {
+            let loaded = unbundle(bundle);
+            let history = get_history(false).await.unwrap_or_else(|e| {
+                log::warn!("Failed loading price history: {e}");
+                Default::default()
+            });
+            Update::Refreshed { loaded, history }
+        }
+        Ok(_) => Update::UpToDate,
+        Err(e) => {
+            log::warn!("Failed checking for newer collections: {e}");
+            Update::Offline
         }
     }
 }
 
-impl<'a, I> CardDeduperExt<'a> for I where I: Sized + Iterator<Item = &'a Entry> {}
+/// Loads the price history and appends today's prices from `data` to it, only rewriting the file
+/// when a price changed
+#[cfg(not(target_arch = "wasm32"))]
+pub fn get_history(data: &Collection) -> anyhow::Result<PriceHistory> {
+    let path = format!("assets/{HISTORY_FILE}");
+    let mut history = match std::fs::File::open(&path) {
+        Ok(file) => PriceHistory::from_reader(file)?,
+        Err(_) => PriceHistory::new(),
+    };
+
+    if history.record(archidekt::today(), data) {
+        std::fs::create_dir_all("assets")?;
+        history.to_writer(std::fs::File::create(&path)?)?;
+    }
+
+    Ok(history)
+}
+
+/// Loads the price history from browser storage when `stored` is set, otherwise fetches it
+#[cfg(target_arch = "wasm32")]
+pub async fn get_history(stored: bool) -> anyhow::Result<PriceHistory> {
+    let path = format!("assets/{HISTORY_FILE}");
+    let bytes = if stored {
+        crate::storage::load(&path).await?
+    } else {
+        fetch_stored(&path).await.ok()
+    };
+
+    match bytes {
+        Some(bytes) => PriceHistory::from_reader(std::io::Cursor::new(bytes)),
+        None => Ok(PriceHistory::new()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    /// Storage in memory, to read and write RON the way eframe persists the app
+    #[derive(Default)]
+    struct Memory(HashMap<String, String>);
+
+    impl eframe::Storage for Memory {
+        fn get_string(&self, key: &str) -> Option<String> {
+            self.0.get(key).cloned()
+        }
+
+        fn set_string(&mut self, key: &str, value: String) {
+            self.0.insert(key.to_string(), value);
+        }
+
+        fn flush(&mut self) {}
+    }
+
+    fn from_ron(ron: &str) -> Option<Search> {
+        let mut storage = Memory::default();
+        eframe::Storage::set_string(&mut storage, "search", ron.to_string());
+        eframe::get_value(&storage, "search")
+    }
+
+    fn colors(s: &str) -> ColorIdent {
+        s.parse().unwrap()
+    }
+
+    /// Which of the cards `mode` lets through when looking for `wanted`
+    fn matching(mode: ColorMatch, wanted: &str) -> Vec<&'static str> {
+        ["C", "B", "G", "R", "BG", "UBG", "BRG"]
+            .into_iter()
+            .filter(|card| mode.test(&colors(wanted), &colors(card)))
+            .collect()
+    }
+
+    #[test]
+    fn exactly() {
+        assert_eq!(matching(ColorMatch::Exactly, "BG"), ["BG"]);
+        assert_eq!(matching(ColorMatch::Exactly, "C"), ["C"]);
+    }
+
+    #[test]
+    fn at_most() {
+        assert_eq!(matching(ColorMatch::AtMost, "BG"), ["C", "B", "G", "BG"]);
+        assert_eq!(matching(ColorMatch::AtMost, "C"), ["C"]);
+    }
+
+    #[test]
+    fn at_least() {
+        assert_eq!(matching(ColorMatch::AtLeast, "BG"), ["BG", "UBG", "BRG"]);
+        assert_eq!(
+            matching(ColorMatch::AtLeast, "C"),
+            ["C", "B", "G", "R", "BG", "UBG", "BRG"]
+        );
+    }
+
+    #[test]
+    fn includes() {
+        assert_eq!(
+            matching(ColorMatch::Includes, "BG"),
+            ["B", "G", "BG", "UBG", "BRG"]
+        );
+        assert!(matching(ColorMatch::Includes, "C").is_empty());
+    }
+
+    #[test]
+    fn color_count() {
+        let count = |count: ColorCount| -> Vec<&str> {
+            ["C", "B", "BG", "UBG", "WUBRG"]
+                .into_iter()
+                .filter(|card| count.test(&colors(card)))
+                .collect()
+        };
+        assert_eq!(count(ColorCount::Any), ["C", "B", "BG", "UBG", "WUBRG"]);
+        assert_eq!(count(ColorCount::Mono), ["B"]);
+        assert_eq!(count(ColorCount::Two), ["BG"]);
+        assert_eq!(count(ColorCount::ThreePlus), ["UBG", "WUBRG"]);
+    }
+
+    #[test]
+    fn cycles_include_exclude_everyone() {
+        let strosel = Owner::from(User::Strosel);
+        let mut owners = Owners::default();
+
+        owners.cycle(strosel.clone());
+        assert_eq!((owners.include.len(), owners.exclude.len()), (1, 0));
+        assert!(owners.test(&strosel) && !owners.test(&User::VikinGG.into()));
+
+        owners.cycle(strosel.clone());
+        assert_eq!((owners.include.len(), owners.exclude.len()), (0, 1));
+        assert!(!owners.test(&strosel) && owners.test(&User::VikinGG.into()));
+
+        owners.cycle(strosel);
+        assert!(owners.is_everyone());
+    }
+
+    #[test]
+    fn cycles_guests_too() {
+        let guest = Owner::Guest("Bob".into());
+        let mut owners = Owners::default();
+        owners.cycle(User::Strosel.into());
+        owners.cycle(guest.clone());
+
+        assert!(owners.test(&guest) && owners.test(&User::Strosel.into()));
+        assert!(!owners.test(&Owner::Guest("Alice".into())));
+    }
+
+    #[test]
+    fn reads_old_single_owner() {
+        let search = from_ron(
+            r#"Single(owner:Some(Strosel),color:"BG",colorless:false,name:"sol",ty:"",set:"")"#,
+        )
+        .unwrap();
+        let Search::Single { owners, name, .. } = search else {
+            panic!("not a single search");
+        };
+        assert_eq!(owners.include, [Owner::from(User::Strosel)]);
+        assert!(owners.exclude.is_empty());
+        assert_eq!(name, "sol");
+    }
+
+    #[test]
+    fn reads_old_wantlist_exclude() {
+        let search =
+            from_ron(r#"Wantlist(list:"Sol Ring",owner:None,exclude:Some(VikinGG))"#).unwrap();
+        assert_eq!(
+            search,
+            Search::Wantlist {
+                list: "Sol Ring".into(),
+                owners: Owners::excluding(Some(User::VikinGG)),
+            }
+        );
+    }
+
+    #[test]
+    fn owners_round_trip() {
+        let search = Search::Wantlist {
+            list: "Sol Ring".into(),
+            owners: Owners {
+                include: vec![User::Strosel.into(), Owner::Guest("Bob".into())],
+                exclude: vec![Owner::Guest("Smith, J".into())],
+            },
+        };
+        let mut storage = Memory::default();
+        eframe::set_value(&mut storage, "search", &search);
+
+        assert_eq!(eframe::get_value(&storage, "search"), Some(search));
+    }
+}
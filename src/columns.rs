@@ -0,0 +1,214 @@
+/// A column of the cards table
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum ColumnKind {
+    /// Whether the card is in the pull lists
+    Pick,
+    Owner,
+    Quantity,
+    ColorId,
+    Name,
+    Type,
+    Set,
+    Scryfall,
+    Price,
+    Free,
+    Decks,
+}
+
+impl ColumnKind {
+    pub const ALL: [Self; 11] = [
+        Self::Pick,
+        Self::Owner,
+        Self::Quantity,
+        Self::ColorId,
+        Self::Name,
+        Self::Type,
+        Self::Set,
+        Self::Scryfall,
+        Self::Price,
+        Self::Free,
+        Self::Decks,
+    ];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            ColumnKind::Pick => "🛒",
+            ColumnKind::Owner => "Owner",
+            ColumnKind::Quantity => "X",
+            ColumnKind::ColorId => "Color Id",
+            ColumnKind::Name => "Name",
+            ColumnKind::Type => "Type",
+            ColumnKind::Set => "Set",
+            ColumnKind::Scryfall => "Scryfall",
+            ColumnKind::Price => "Price",
+            ColumnKind::Free => "Free",
+            ColumnKind::Decks => "Decks",
+        }
+    }
+
+    /// What the column is called in the column picker, where an icon alone won't do
+    pub fn label(&self) -> &'static str {
+        match self {
+            ColumnKind::Pick => "Pull list",
+            ColumnKind::Quantity => "Quantity",
+            _ => self.title(),
+        }
+    }
+
+    fn width(&self) -> f32 {
+        match self {
+            ColumnKind::Pick => 20.0,
+            ColumnKind::Owner => 90.0,
+            ColumnKind::Quantity => 20.0,
+            ColumnKind::ColorId => 100.0,
+            ColumnKind::Name => 200.0,
+            ColumnKind::Type => 200.0,
+            ColumnKind::Set => 50.0,
+            ColumnKind::Scryfall => 250.0,
+            ColumnKind::Price => 70.0,
+            ColumnKind::Free => 40.0,
+            ColumnKind::Decks => 200.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Column {
+    pub kind: ColumnKind,
+    pub visible: bool,
+    pub width: f32,
+}
+
+/// The order, visibility and width of the cards table's columns
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Columns(Vec<Column>);
+
+impl Default for Columns {
+    fn default() -> Self {
+        Self(
+            ColumnKind::ALL
+                .into_iter()
+                .map(|kind| Column {
+                    kind,
+                    visible: true,
+                    width: kind.width(),
+                })
+                .collect(),
+        )
+    }
+}
+
+impl Columns {
+    /// Every column in order, including hidden ones
+    pub fn all_mut(&mut self) -> impl Iterator<Item = &mut Column> {
+        self.0.iter_mut()
+    }
+
+    pub fn visible(&self) -> impl Iterator<Item = &Column> {
+        self.0.iter().filter(|column| column.visible)
+    }
+
+    pub fn set_width(&mut self, kind: ColumnKind, width: f32) {
+        if let Some(column) = self.0.iter_mut().find(|column| column.kind == kind) {
+            column.width = width;
+        }
+    }
+
+    /// Moves the column `kind` to where `target` is
+    pub fn move_to(&mut self, kind: ColumnKind, target: ColumnKind) {
+        let Some(from) = self.0.iter().position(|column| column.kind == kind) else {
+            return;
+        };
+        let Some(to) = self.0.iter().position(|column| column.kind == target) else {
+            return;
+        };
+        let column = self.0.remove(from);
+        self.0.insert(to, column);
+    }
+
+    /// Drops duplicates and adds columns missing from a layout saved by an older version
+    pub fn repair(mut self) -> Self {
+        let mut seen = Vec::new();
+        self.0.retain(|column| {
+            let new = !seen.contains(&column.kind);
+            seen.push(column.kind);
+            new
+        });
+        for kind in ColumnKind::ALL {
+            if !seen.contains(&kind) {
+                self.0.push(Column {
+                    kind,
+                    visible: true,
+                    width: kind.width(),
+                });
+            }
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn order(columns: &Columns) -> Vec<ColumnKind> {
+        columns.0.iter().map(|column| column.kind).collect()
+    }
+
+    fn column(kind: ColumnKind, visible: bool) -> Column {
+        Column {
+            kind,
+            visible,
+            width: 10.0,
+        }
+    }
+
+    #[test]
+    fn repair_drops_duplicates_and_adds_new_kinds() {
+        let saved = Columns(vec![
+            column(ColumnKind::Name, false),
+            column(ColumnKind::Owner, true),
+            column(ColumnKind::Name, true),
+        ]);
+        let repaired = saved.repair();
+
+        assert_eq!(repaired.0.len(), ColumnKind::ALL.len());
+        assert_eq!(repaired.0[0], column(ColumnKind::Name, false));
+        assert_eq!(repaired.0[1], column(ColumnKind::Owner, true));
+        let added: Vec<ColumnKind> = ColumnKind::ALL
+            .into_iter()
+            .filter(|kind| !matches!(kind, ColumnKind::Name | ColumnKind::Owner))
+            .collect();
+        assert_eq!(order(&repaired)[2..], added);
+        assert!(repaired.0[2..].iter().all(|column| column.visible));
+    }
+
+    #[test]
+    fn repair_keeps_a_whole_layout() {
+        let mut columns = Columns::default();
+        columns.move_to(ColumnKind::Price, ColumnKind::Pick);
+        columns.set_width(ColumnKind::Name, 123.0);
+
+        assert_eq!(columns.clone().repair(), columns);
+    }
+
+    #[test]
+    fn moves_forwards_and_backwards() {
+        use ColumnKind::*;
+        let mut columns = Columns(vec![
+            column(Pick, true),
+            column(Owner, true),
+            column(Name, true),
+            column(Price, true),
+        ]);
+
+        columns.move_to(Owner, Price);
+        assert_eq!(order(&columns), [Pick, Name, Price, Owner]);
+
+        columns.move_to(Owner, Pick);
+        assert_eq!(order(&columns), [Owner, Pick, Name, Price]);
+
+        columns.move_to(Name, Name);
+        assert_eq!(order(&columns), [Owner, Pick, Name, Price]);
+    }
+}
@@ -0,0 +1,192 @@
+use std::collections::HashMap;
+
+use crate::index::normalize;
+use archidekt::{Deck, Entry, Owner};
+
+/// Set, Scryfall ID and how many copies the owner has of it
+type Printing<'a> = (&'a str, &'a str, u32);
+
+/// How an owner's copies of a card, across all printings, are spread over their decks
+#[derive(Debug, Default, Clone)]
+pub struct Usage {
+    /// Names of the decks using the card and how many copies each
+    pub decks: Vec<(String, u32)>,
+    pub owned: u32,
+    pub in_decks: u32,
+    /// Deck copies of each printing by Scryfall ID, as far as the decks say
+    pinned: HashMap<String, u32>,
+    /// Free copies of each printing by set and Scryfall ID
+    printings: HashMap<(String, String), u32>,
+}
+
+impl Usage {
+    pub fn free(&self) -> u32 {
+        self.owned.saturating_sub(self.in_decks)
+    }
+
+    /// Decides which printings the deck copies are, so the free copies of all printings add up
+    /// to `free`
+    ///
+    /// Printings the decks name are used first, the rest of the deck copies are taken from the
+    /// printings in collection order
+    fn share_out(&mut self, printings: &[Printing]) {
+        let mut left = self.in_decks;
+        let mut used: Vec<u32> = printings
+            .iter()
+            .map(|(_, scryfall, quantity)| {
+                let take = self
+                    .pinned
+                    .get(*scryfall)
+                    .map_or(0, |pinned| (*pinned).min(*quantity).min(left));
+                left -= take;
+                take
+            })
+            .collect();
+        for ((_, _, quantity), used) in printings.iter().zip(&mut used) {
+            let take = (quantity - *used).min(left);
+            *used += take;
+            left -= take;
+        }
+
+        self.printings = printings
+            .iter()
+            .zip(used)
+            .map(|((set, scryfall, quantity), used)| {
+                ((set.to_string(), scryfall.to_string()), quantity - used)
+            })
+            .collect();
+    }
+}
+
+/// Which cards are sleeved up in their owner's decks, rebuilt whenever the collection changes
+#[derive(Debug, Default)]
+pub struct DeckUsage(HashMap<Owner, HashMap<String, Usage>>);
+
+impl DeckUsage {
+    pub fn new(data: &[Entry], decks: &[Deck]) -> Self {
+        let mut usage: HashMap<Owner, HashMap<String, Usage>> = HashMap::new();
+
+        for deck in decks {
+            let cards = usage.entry(deck.owner.into()).or_default();
+            for card in &deck.cards {
+                let card_usage = cards.entry(normalize(&card.name)).or_default();
+                card_usage.in_decks += card.quantity;
+                if !card.scryfall.is_empty() {
+                    *card_usage.pinned.entry(card.scryfall.clone()).or_default() += card.quantity;
+                }
+                match card_usage
+                    .decks
+                    .iter_mut()
+                    .find(|(name, _)| *name == deck.name)
+                {
+                    Some((_, quantity)) => *quantity += card.quantity,
+                    None => card_usage.decks.push((deck.name.clone(), card.quantity)),
+                }
+            }
+        }
+
+        // Only cards that are in some deck are worth tracking
+        let mut owned: HashMap<(&Owner, String), Vec<Printing>> = HashMap::new();
+        for entry in data {
+            let name = normalize(&entry.name);
+            let Some(card_usage) = usage
+                .get_mut(&entry.owner)
+                .and_then(|cards| cards.get_mut(&name))
+            else {
+                continue;
+            };
+            card_usage.owned += entry.quantity;
+
+            let printings = owned.entry((&entry.owner, name)).or_default();
+            match printings
+                .iter_mut()
+                .find(|(set, scryfall, _)| *set == entry.set && *scryfall == entry.scryfall)
+            {
+                Some((_, _, quantity)) => *quantity += entry.quantity,
+                None => printings.push((&entry.set, &entry.scryfall, entry.quantity)),
+            }
+        }
+
+        for ((owner, name), printings) in owned {
+            if let Some(card_usage) = usage.get_mut(owner).and_then(|cards| cards.get_mut(&name)) {
+                card_usage.share_out(&printings);
+            }
+        }
+
+        Self(usage)
+    }
+
+    /// `None` when the card isn't in any of its owner's decks
+    pub fn get(&self, entry: &Entry) -> Option<&Usage> {
+        self.0.get(&entry.owner)?.get(&normalize(&entry.name))
+    }
+
+    /// Copies of this printing the owner could lend out, see `Usage::share_out`
+    pub fn free(&self, entry: &Entry) -> u32 {
+        self.get(entry).map_or(entry.quantity, |usage| {
+            usage
+                .printings
+                .get(&(entry.set.clone(), entry.scryfall.clone()))
+                .map_or(0, |free| (*free).min(entry.quantity))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use archidekt::{ColorIdent, DeckCard, User};
+
+    fn entry(set: &str, scryfall: &str, quantity: u32) -> Entry {
+        Entry {
+            owner: User::Strosel.into(),
+            quantity,
+            color_identity: ColorIdent::new(),
+            name: "Sol Ring".into(),
+            ty: "Artifact".into(),
+            set: set.into(),
+            scryfall: scryfall.into(),
+            price: None,
+        }
+    }
+
+    fn deck(scryfall: &str, quantity: u32) -> Deck {
+        Deck {
+            id: 1,
+            name: "Artifacts".into(),
+            owner: User::Strosel,
+            cards: vec![DeckCard {
+                quantity,
+                name: "Sol Ring".into(),
+                set: String::new(),
+                scryfall: scryfall.into(),
+            }],
+        }
+    }
+
+    #[test]
+    fn deck_copies_count_once() {
+        let data = [entry("cmd", "a", 1), entry("c21", "b", 1)];
+        let usage = DeckUsage::new(&data, &[deck("", 1)]);
+
+        let free: u32 = data.iter().map(|entry| usage.free(entry)).sum();
+        assert_eq!(free, 1);
+    }
+
+    #[test]
+    fn deck_printing_is_used_first() {
+        let data = [entry("cmd", "a", 1), entry("c21", "b", 2)];
+        let usage = DeckUsage::new(&data, &[deck("b", 1)]);
+
+        assert_eq!(usage.free(&data[0]), 1);
+        assert_eq!(usage.free(&data[1]), 1);
+    }
+
+    #[test]
+    fn cards_in_no_deck_are_free() {
+        let data = [entry("cmd", "a", 3)];
+        let usage = DeckUsage::new(&data, &[]);
+
+        assert_eq!(usage.free(&data[0]), 3);
+    }
+}
@@ -0,0 +1,243 @@
+use std::collections::HashMap;
+
+use crate::decks::DeckUsage;
+use archidekt::{Entry, Owner};
+
+/// Identifies a group between frames, e.g. to remember which ones are expanded
+pub type GroupKey = (Option<Owner>, String);
+
+/// Entries of the same card shown as one row, either one owner's printings or the whole pod's
+#[derive(Debug, Clone)]
+pub struct Group<'a> {
+    /// `None` when grouped across owners
+    pub owner: Option<&'a Owner>,
+    pub entries: Vec<&'a Entry>,
+}
+
+impl<'a> Group<'a> {
+    pub fn key(&self) -> GroupKey {
+        (self.owner.cloned(), self.first().name.clone())
+    }
+
+    pub fn first(&self) -> &'a Entry {
+        self.entries[0]
+    }
+
+    pub fn quantity(&self) -> u32 {
+        self.entries.iter().map(|entry| entry.quantity).sum()
+    }
+
+    /// Owners and how many copies each has, most first
+    pub fn owners(&self) -> Vec<(&'a Owner, u32)> {
+        let mut owners: Vec<(&Owner, u32)> = Vec::new();
+        for entry in &self.entries {
+            match owners.iter_mut().find(|(owner, _)| **owner == entry.owner) {
+                Some((_, quantity)) => *quantity += entry.quantity,
+                None => owners.push((&entry.owner, entry.quantity)),
+            }
+        }
+        owners.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        owners
+    }
+
+    /// The cheapest and the most expensive printing, `None` when none have a price
+    pub fn prices(&self) -> Option<(f32, f32)> {
+        self.entries
+            .iter()
+            .filter_map(|entry| entry.price)
+            .fold(None, |range, price| match range {
+                Some((min, max)) => Some((price.min(min), price.max(max))),
+                None => Some((price, price)),
+            })
+    }
+
+    /// Set codes in the order they first appear
+    pub fn sets(&self) -> Vec<&'a str> {
+        let mut sets: Vec<&str> = Vec::new();
+        for entry in &self.entries {
+            if !sets.contains(&entry.set.as_str()) {
+                sets.push(&entry.set);
+            }
+        }
+        sets
+    }
+
+    /// Copies of the group's printings the owners could lend out, see `DeckUsage::free`
+    pub fn free(&self, usage: &DeckUsage) -> u32 {
+        self.entries.iter().map(|entry| usage.free(entry)).sum()
+    }
+}
+
+/// A group kept between frames, as indices into the collection
+#[derive(Debug, Clone)]
+pub struct Grouped {
+    across_owners: bool,
+    pub entries: Vec<usize>,
+}
+
+impl Grouped {
+    pub fn resolve<'a>(&self, data: &'a [Entry]) -> Group<'a> {
+        let entries: Vec<&Entry> = self.entries.iter().map(|&i| &data[i]).collect();
+        Group {
+            owner: (!self.across_owners).then(|| &entries[0].owner),
+            entries,
+        }
+    }
+}
+
+/// Groups the `results` by card name, and by owner too unless `across_owners`, no matter their
+/// order
+pub fn group(data: &[Entry], results: &[usize], across_owners: bool) -> Vec<Grouped> {
+    let mut groups: Vec<Grouped> = Vec::new();
+    let mut index: HashMap<(Option<&Owner>, &str), usize> = HashMap::new();
+
+    for &i in results {
+        let entry = &data[i];
+        let owner = (!across_owners).then_some(&entry.owner);
+        match index.get(&(owner, entry.name.as_str())) {
+            Some(&group) => groups[group].entries.push(i),
+            None => {
+                index.insert((owner, &entry.name), groups.len());
+                groups.push(Grouped {
+                    across_owners,
+                    entries: vec![i],
+                });
+            }
+        }
+    }
+
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use archidekt::{ColorIdent, Deck, DeckCard, User};
+
+    fn entry(owner: impl Into<Owner>, name: &str, set: &str, quantity: u32) -> Entry {
+        Entry {
+            owner: owner.into(),
+            quantity,
+            color_identity: ColorIdent::new(),
+            name: name.into(),
+            ty: String::new(),
+            set: set.into(),
+            scryfall: set.into(),
+            price: None,
+        }
+    }
+
+    fn groups(data: &[Entry], across_owners: bool) -> Vec<Group<'_>> {
+        let results: Vec<usize> = (0..data.len()).collect();
+        group(data, &results, across_owners)
+            .iter()
+            .map(|grouped| grouped.resolve(data))
+            .collect()
+    }
+
+    fn sets<'a>(group: &Group<'a>) -> Vec<&'a str> {
+        group
+            .entries
+            .iter()
+            .map(|entry| entry.set.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn doesnt_depend_on_order() {
+        let data = [
+            entry(User::Strosel, "Sol Ring", "cmd", 1),
+            entry(User::Strosel, "Mind Stone", "c21", 1),
+            entry(User::Strosel, "Sol Ring", "c21", 1),
+            entry(User::VikinGG, "Mind Stone", "cmd", 1),
+            entry(User::Strosel, "Sol Ring", "ltc", 1),
+        ];
+        let groups = groups(&data, false);
+
+        assert_eq!(groups.len(), 3);
+        assert_eq!(sets(&groups[0]), ["cmd", "c21", "ltc"]);
+        assert_eq!(sets(&groups[1]), ["c21"]);
+        assert_eq!(sets(&groups[2]), ["cmd"]);
+    }
+
+    #[test]
+    fn keys_per_owner_or_across_owners() {
+        let data = [
+            entry(User::Strosel, "Sol Ring", "cmd", 1),
+            entry(User::VikinGG, "Sol Ring", "c21", 1),
+        ];
+
+        let per_owner = groups(&data, false);
+        assert_eq!(
+            per_owner.iter().map(Group::key).collect::<Vec<_>>(),
+            [
+                (Some(User::Strosel.into()), "Sol Ring".to_string()),
+                (Some(User::VikinGG.into()), "Sol Ring".to_string()),
+            ]
+        );
+
+        let across = groups(&data, true);
+        assert_eq!(across.len(), 1);
+        assert_eq!(across[0].key(), (None, "Sol Ring".to_string()));
+        assert_eq!(across[0].quantity(), 2);
+    }
+
+    #[test]
+    fn owners_with_most_copies_first() {
+        let data = [
+            entry(User::VikinGG, "Sol Ring", "cmd", 1),
+            entry(User::Strosel, "Sol Ring", "cmd", 1),
+            entry(User::Amon8808, "Sol Ring", "cmd", 1),
+            entry(User::Amon8808, "Sol Ring", "c21", 2),
+        ];
+        let owners = groups(&data, true)[0].owners();
+
+        assert_eq!(
+            owners,
+            [
+                (&User::Amon8808.into(), 3),
+                (&User::Strosel.into(), 1),
+                (&User::VikinGG.into(), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn prices_skip_unpriced_printings() {
+        let mut data = [
+            entry(User::Strosel, "Sol Ring", "cmd", 1),
+            entry(User::Strosel, "Sol Ring", "c21", 1),
+            entry(User::Strosel, "Sol Ring", "ltc", 1),
+        ];
+        assert_eq!(groups(&data, false)[0].prices(), None);
+
+        data[0].price = Some(3.0);
+        data[2].price = Some(1.0);
+        assert_eq!(groups(&data, false)[0].prices(), Some((1.0, 3.0)));
+    }
+
+    #[test]
+    fn free_counts_only_the_groups_printings() {
+        let data = [
+            entry(User::Strosel, "Sol Ring", "cmd", 1),
+            entry(User::Strosel, "Sol Ring", "c21", 2),
+        ];
+        let deck = Deck {
+            id: 1,
+            name: "Artifacts".into(),
+            owner: User::Strosel,
+            cards: vec![DeckCard {
+                quantity: 1,
+                name: "Sol Ring".into(),
+                set: String::new(),
+                scryfall: "c21".into(),
+            }],
+        };
+        let usage = DeckUsage::new(&data, &[deck]);
+
+        assert_eq!(groups(&data, false)[0].free(&usage), 2);
+        // Like when a set filter leaves only one of the printings
+        let c21 = group(&data, &[1], false)[0].resolve(&data);
+        assert_eq!(c21.free(&usage), 1);
+    }
+}
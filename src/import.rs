@@ -0,0 +1,165 @@
+use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
+
+use archidekt::{ColumnMap, CsvFormat, DeckList, Owner, Parsed, User};
+use eframe::egui;
+use strum::VariantArray;
+
+/// A CSV file waiting to be merged into the collection as a guest owner
+pub struct Import {
+    pub name: String,
+    pub headers: Vec<String>,
+    pub columns: ColumnMap,
+    pub format: Option<CsvFormat>,
+    pub error: Option<String>,
+    data: String,
+}
+
+impl Import {
+    pub fn new(file_name: &str, bytes: Vec<u8>) -> Self {
+        let name = file_name
+            .rsplit_once('.')
+            .map_or(file_name, |(stem, _)| stem)
+            .to_string();
+
+        let (data, headers) = match String::from_utf8(bytes)
+            .map_err(anyhow::Error::from)
+            .and_then(|data| Ok((archidekt::headers(&data)?, data)))
+        {
+            Ok((headers, data)) => (data, headers),
+            Err(e) => {
+                return Self {
+                    name,
+                    headers: Vec::new(),
+                    columns: ColumnMap::default(),
+                    format: None,
+                    error: Some(format!("Not a CSV file: {e}")),
+                    data: String::new(),
+                }
+            }
+        };
+
+        let format = CsvFormat::detect(&headers);
+        let mut columns =
+            format.map_or_else(|| ColumnMap::guess(&headers), |format| format.columns());
+        // Formats map some columns only found with certain export settings
+        for column in [&mut columns.price, &mut columns.ty, &mut columns.color] {
+            if column
+                .as_ref()
+                .is_some_and(|column| !headers.contains(column))
+            {
+                *column = None;
+            }
+        }
+
+        Self {
+            name,
+            headers,
+            columns,
+            format,
+            error: None,
+            data,
+        }
+    }
+
+    pub fn owner(&self) -> Owner {
+        Owner::Guest(self.name.trim().to_string())
+    }
+
+    pub fn read(&self) -> anyhow::Result<Parsed> {
+        let name = self.name.trim();
+        if name.is_empty() {
+            anyhow::bail!("The guest needs a name");
+        }
+        // A guest named after a member would look just like them, and shared links would turn
+        // the guest into the member
+        if let Some(user) = User::VARIANTS
+            .iter()
+            .find(|user| user.to_string().eq_ignore_ascii_case(name))
+        {
+            anyhow::bail!("{user} is in the pod, pick another name for the guest");
+        }
+        if self.columns.name.is_empty() {
+            anyhow::bail!("Pick the column holding card names");
+        }
+        self.columns.read(self.owner(), self.data.as_bytes())
+    }
+}
+
+type FetchedDeck = Result<DeckList, String>;
+
+/// Archidekt's API doesn't allow requests from other sites, so the web build can't fetch decks
+pub const CAN_IMPORT_DECKS: bool = cfg!(not(target_arch = "wasm32"));
+
+/// The deck URL field of the wantlist mode, and the fetch in flight
+#[derive(Default)]
+pub struct DeckImport {
+    pub input: String,
+    /// Whether the maybeboard and sideboard are wanted too
+    pub boards: bool,
+    /// What came of the last import
+    pub status: Option<Result<String, String>>,
+    pending: Option<Receiver<FetchedDeck>>,
+}
+
+impl DeckImport {
+    pub fn is_pending(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    pub fn start(&mut self, ctx: &egui::Context) {
+        let Some(id) = archidekt::parse_deck_id(&self.input) else {
+            self.status = Some(Err("Not an Archidekt deck URL or ID".into()));
+            return;
+        };
+
+        let (sender, receiver) = channel();
+        self.pending = Some(receiver);
+        self.status = None;
+        fetch_deck(id, sender, ctx.clone());
+    }
+
+    /// The deck, once it has arrived
+    pub fn poll(&mut self) -> Option<DeckList> {
+        let fetched = match self.pending.as_ref()?.try_recv() {
+            Ok(fetched) => fetched,
+            Err(TryRecvError::Empty) => return None,
+            Err(TryRecvError::Disconnected) => Err("The deck fetch was interrupted".into()),
+        };
+        self.pending = None;
+
+        match fetched {
+            Ok(deck) => {
+                let by = match deck.member {
+                    Some(member) => format!(", leaving out {member}'s own cards"),
+                    None if deck.owner.is_empty() => String::new(),
+                    None => format!(" by {}", deck.owner),
+                };
+                self.status = Some(Ok(format!("Imported {}{by}", deck.name)));
+                Some(deck)
+            }
+            Err(e) => {
+                self.status = Some(Err(e));
+                None
+            }
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn fetch_deck(id: u32, sender: Sender<FetchedDeck>, ctx: egui::Context) {
+    std::thread::spawn(move || {
+        let fetched = crate::collection::client()
+            .deck_list(id)
+            .map_err(|e| e.to_string());
+        sender.send(fetched).ok();
+        ctx.request_repaint();
+    });
+}
+
+#[cfg(target_arch = "wasm32")]
+fn fetch_deck(_id: u32, sender: Sender<FetchedDeck>, ctx: egui::Context) {
+    sender
+        .send(Err("Importing decks needs the desktop app".into()))
+        .ok();
+    ctx.request_repaint();
+}
@@ -0,0 +1,360 @@
+use crate::collection::Search;
+use archidekt::Entry;
+
+/// Lowercases and folds accents and curly quotes, so `Lim-Dûl’s` matches `lim-dul's`
+pub fn normalize(s: &str) -> String {
+    s.chars()
+        .flat_map(char::to_lowercase)
+        .map(|c| match c {
+            'à' | 'á' | 'â' | 'ä' | 'ã' | 'å' => 'a',
+            'é' | 'è' | 'ê' | 'ë' => 'e',
+            'í' | 'ì' | 'î' | 'ï' => 'i',
+            'ó' | 'ò' | 'ô' | 'ö' | 'õ' => 'o',
+            'ú' | 'ù' | 'û' | 'ü' => 'u',
+            'ñ' => 'n',
+            '’' | '‘' => '\'',
+            c => c,
+        })
+        .collect()
+}
+
+/// Strips a leading count like `2 ` or `2x ` off a wantlist line
+pub fn strip_quantity(line: &str) -> &str {
+    split_quantity(line).1
+}
+
+/// Splits a wantlist line like `2x Sol Ring` into its count and name, the count defaults to 1
+pub fn split_quantity(line: &str) -> (u32, &str) {
+    let line = line.trim();
+    let rest = line.trim_start_matches(|c: char| c.is_ascii_digit());
+    if rest.len() == line.len() {
+        return (1, line);
+    }
+    let count = &line[..line.len() - rest.len()];
+    let rest = rest.strip_prefix(['x', 'X']).unwrap_or(rest);
+    match (rest.strip_prefix(char::is_whitespace), count.parse()) {
+        (Some(name), Ok(count)) => (count, name.trim_start()),
+        // A name that starts with digits, like "7th Pride"
+        _ => (1, line),
+    }
+}
+
+/// Pulls `owner:Name` and `-owner:Name` terms out of a search, quoting names with spaces like
+/// `owner:"Smith, J"`, as the rest of the search and the normalized names to include and exclude
+fn owner_terms(text: &str) -> (String, Vec<String>, Vec<String>) {
+    fn term<'a>(s: &'a str, prefix: &str) -> Option<&'a str> {
+        s.get(..prefix.len())
+            .filter(|start| start.eq_ignore_ascii_case(prefix))
+            .map(|_| &s[prefix.len()..])
+    }
+
+    let mut rest = Vec::new();
+    let mut include = Vec::new();
+    let mut exclude = Vec::new();
+
+    let mut s = text.trim_start();
+    while !s.is_empty() {
+        let (list, after) = match (term(s, "-owner:"), term(s, "owner:")) {
+            (Some(after), _) => (Some(&mut exclude), after),
+            (None, Some(after)) => (Some(&mut include), after),
+            (None, None) => (None, s),
+        };
+        let quoted = after
+            .strip_prefix('"')
+            .filter(|_| list.is_some())
+            .map(|inner| inner.split_once('"').unwrap_or((inner, "")));
+        let (word, next) = quoted.unwrap_or_else(|| {
+            after.split_at(after.find(char::is_whitespace).unwrap_or(after.len()))
+        });
+        match list {
+            Some(list) if !word.trim().is_empty() => list.push(normalize(word.trim())),
+            Some(_) => {}
+            None => rest.push(word),
+        }
+        s = next.trim_start();
+    }
+
+    (rest.join(" "), include, exclude)
+}
+
+/// The normalized search fields of an entry
+struct Key {
+    name: String,
+    types: Vec<String>,
+    set: String,
+    owner: String,
+}
+
+impl Key {
+    fn new(entry: &Entry) -> Self {
+        Self {
+            name: normalize(&entry.name),
+            types: entry.ty.split(',').map(normalize).collect(),
+            set: normalize(&entry.set),
+            owner: normalize(&entry.owner.to_string()),
+        }
+    }
+}
+
+/// Normalized search fields of every entry, built once whenever the collection changes
+#[derive(Default)]
+pub struct SearchIndex {
+    keys: Vec<Key>,
+}
+
+impl SearchIndex {
+    pub fn new(data: &[Entry]) -> Self {
+        Self {
+            keys: data.iter().map(Key::new).collect(),
+        }
+    }
+
+    /// Indices into `data` of the entries matching `search`
+    pub fn search(&self, data: &[Entry], search: &Search) -> Vec<usize> {
+        let query = Query::new(search);
+        data.iter()
+            .zip(&self.keys)
+            .enumerate()
+            .filter(|(_, (entry, key))| query.matches(entry, key))
+            .map(|(i, _)| i)
+            .collect()
+    }
+}
+
+/// A search with its text inputs normalized once up front rather than once per entry
+struct Query<'a> {
+    search: &'a Search,
+    name: String,
+    types: Vec<String>,
+    set: String,
+    wants: Vec<String>,
+    /// Owners from `owner:` terms in the search, on top of the owner chips
+    owners: Vec<String>,
+    /// Owners from `-owner:` terms
+    not_owners: Vec<String>,
+}
+
+impl<'a> Query<'a> {
+    fn new(search: &'a Search) -> Self {
+        let mut query = Self {
+            search,
+            name: String::new(),
+            types: Vec::new(),
+            set: String::new(),
+            wants: Vec::new(),
+            owners: Vec::new(),
+            not_owners: Vec::new(),
+        };
+
+        match search {
+            Search::Single { name, ty, set, .. } => {
+                let (rest, owners, not_owners) = owner_terms(name);
+                // Keeps the search as typed, trailing spaces and all, when it has no owner terms
+                query.name = if owners.is_empty() && not_owners.is_empty() {
+                    normalize(name)
+                } else {
+                    normalize(&rest)
+                };
+                query.owners = owners;
+                query.not_owners = not_owners;
+                query.types = ty
+                    .split(|c: char| !c.is_alphabetic())
+                    .filter(|s| !s.is_empty())
+                    .map(normalize)
+                    .collect();
+                query.set = normalize(set);
+            }
+            Search::Wantlist { list, .. } => {
+                query.wants = list
+                    .lines()
+                    .map(|want| normalize(strip_quantity(want)))
+                    .filter(|want| !want.is_empty())
+                    .collect();
+            }
+        }
+
+        query
+    }
+
+    fn matches(&self, data: &Entry, key: &Key) -> bool {
+        match self.search {
+            Search::Single {
+                owners,
+                color,
+                colorless,
+                color_match,
+                color_count,
+                ..
+            } => {
+                let match_owner = owners.test(&data.owner)
+                    && (self.owners.is_empty() || self.owners.contains(&key.owner))
+                    && !self.not_owners.contains(&key.owner);
+
+                let match_ident = if *colorless {
+                    data.color_identity.is_colorless()
+                } else if color.is_colorless() {
+                    true
+                } else {
+                    color_match.test(color, &data.color_identity)
+                };
+                let match_count = color_count.test(&data.color_identity);
+
+                let match_name = key.name.contains(&self.name);
+
+                let match_types = self
+                    .types
+                    .iter()
+                    .all(|t1| key.types.iter().any(|t2| t2.contains(t1)));
+
+                let match_set = key.set.contains(&self.set);
+
+                [
+                    match_owner,
+                    match_ident,
+                    match_count,
+                    match_name,
+                    match_types,
+                    match_set,
+                ]
+                .into_iter()
+                .all(|x| x)
+            }
+            Search::Wantlist { owners, .. } => {
+                owners.test(&data.owner) && self.wants.iter().any(|want| key.name.contains(want))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collection::{ColorCount, ColorMatch, Owners};
+    use archidekt::{ColorIdent, Owner, User};
+
+    fn entry(owner: Owner, name: &str) -> Entry {
+        Entry {
+            owner,
+            quantity: 1,
+            color_identity: ColorIdent::new(),
+            name: name.into(),
+            ty: "Artifact".into(),
+            set: "c21".into(),
+            scryfall: String::new(),
+            price: None,
+        }
+    }
+
+    fn single(name: &str) -> Search {
+        Search::Single {
+            owners: Owners::default(),
+            color: ColorIdent::new(),
+            colorless: false,
+            color_match: ColorMatch::default(),
+            color_count: ColorCount::default(),
+            name: name.into(),
+            ty: String::new(),
+            set: String::new(),
+        }
+    }
+
+    fn owners_of(search: &str) -> Vec<String> {
+        let data = [
+            entry(User::Strosel.into(), "Sol Ring"),
+            entry(User::VikinGG.into(), "Sol Ring"),
+            entry(Owner::Guest("Smith, J".into()), "Sol Ring"),
+            entry(User::Strosel.into(), "Mind Stone"),
+        ];
+        SearchIndex::new(&data)
+            .search(&data, &single(search))
+            .into_iter()
+            .map(|i| format!("{} {}", data[i].owner, data[i].name))
+            .collect()
+    }
+
+    fn wantlist(list: &str, data: &[Entry]) -> Vec<String> {
+        let search = Search::Wantlist {
+            list: list.into(),
+            owners: Owners::default(),
+        };
+        SearchIndex::new(data)
+            .search(data, &search)
+            .into_iter()
+            .map(|i| data[i].name.clone())
+            .collect()
+    }
+
+    #[test]
+    fn splits_quantities() {
+        assert_eq!(split_quantity("2x Sol Ring"), (2, "Sol Ring"));
+        assert_eq!(split_quantity("2X Sol Ring"), (2, "Sol Ring"));
+        assert_eq!(split_quantity("1 sol ring"), (1, "sol ring"));
+        assert_eq!(split_quantity("  10   Island  "), (10, "Island"));
+        assert_eq!(split_quantity("Sol Ring"), (1, "Sol Ring"));
+        assert_eq!(split_quantity("7th Pride"), (1, "7th Pride"));
+        assert_eq!(split_quantity("2x4 Lumber"), (1, "2x4 Lumber"));
+        assert_eq!(split_quantity(""), (1, ""));
+        assert_eq!(split_quantity("   "), (1, ""));
+
+        assert_eq!(strip_quantity("2x Sol Ring"), "Sol Ring");
+        assert_eq!(strip_quantity("4"), "4");
+    }
+
+    #[test]
+    fn normalizes_case_accents_and_quotes() {
+        assert_eq!(normalize("Lim-Dûl’s Vault"), "lim-dul's vault");
+        assert_eq!(normalize("Séance"), "seance");
+        assert_eq!(normalize("Jötun Grunt"), "jotun grunt");
+        assert_eq!(normalize("SOL RING"), "sol ring");
+    }
+
+    #[test]
+    fn wantlists_match_quantities_faces_and_accents() {
+        let data = [
+            entry(User::Strosel.into(), "Sol Ring"),
+            entry(User::Strosel.into(), "Fire // Ice"),
+            entry(User::Strosel.into(), "Lim-Dûl's Vault"),
+            entry(User::Strosel.into(), "Mind Stone"),
+        ];
+
+        assert_eq!(
+            wantlist("2x Sol Ring\n\n  \nfire\n1 lim-dul’s vault\n", &data),
+            ["Sol Ring", "Fire // Ice", "Lim-Dûl's Vault"]
+        );
+        assert!(wantlist("\n\n", &data).is_empty());
+    }
+
+    #[test]
+    fn pulls_out_owner_terms() {
+        assert_eq!(
+            owner_terms(r#"sol owner:Strosel ring -OWNER:"Smith, J" owner:"#),
+            (
+                "sol ring".to_string(),
+                vec!["strosel".to_string()],
+                vec!["smith, j".to_string()]
+            )
+        );
+        assert_eq!(
+            owner_terms("owners are nice"),
+            ("owners are nice".to_string(), vec![], vec![])
+        );
+    }
+
+    #[test]
+    fn searches_by_owner() {
+        assert_eq!(
+            owners_of("owner:strosel"),
+            ["Strosel Sol Ring", "Strosel Mind Stone"]
+        );
+        assert_eq!(owners_of("ring owner:Strosel"), ["Strosel Sol Ring"]);
+        assert_eq!(
+            owners_of("ring owner:Strosel owner:VikinGG"),
+            ["Strosel Sol Ring", "VikinGG Sol Ring"]
+        );
+        assert_eq!(
+            owners_of("ring -owner:Strosel"),
+            ["VikinGG Sol Ring", "Smith, J Sol Ring"]
+        );
+        assert_eq!(owners_of(r#"owner:"smith, j""#), ["Smith, J Sol Ring"]);
+    }
+}
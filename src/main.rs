@@ -1,11 +1,23 @@
 pub mod app;
+pub mod cart;
+pub mod chart;
 pub mod collection;
+pub mod columns;
+pub mod decks;
+pub mod group;
+pub mod import;
+pub mod index;
 pub mod loader;
+pub mod plan;
+pub mod saved;
+pub mod stats;
+pub mod trades;
+pub mod wants;
 
 #[cfg(not(target_arch = "wasm32"))]
 fn main() -> Result<(), eframe::Error> {
     use app::App;
-    use collection::get_collections;
+    use collection::{get_collections, get_history};
 
     env_logger::init(); // Log to stderr (if you run with `RUST_LOG=debug`).
     let options = eframe::NativeOptions {
@@ -13,11 +25,23 @@ fn main() -> Result<(), eframe::Error> {
         ..Default::default()
     };
 
-    let data = get_collections().expect("Failed fetching collections");
+    let loaded = get_collections().expect("Failed fetching collections");
+    let history = get_history(&loaded.data).unwrap_or_else(|e| {
+        log::warn!("Failed updating price history: {e}");
+        Default::default()
+    });
 
-    eframe::run_native("My egui App", options, App::creator(data))
+    eframe::run_native(
+        "My egui App",
+        options,
+        App::creator(loaded, history, Default::default()),
+    )
 }
 
+#[cfg(any(target_arch = "wasm32", test))]
+mod share;
+#[cfg(target_arch = "wasm32")]
+mod storage;
 #[cfg(target_arch = "wasm32")]
 mod web;
 
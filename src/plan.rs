@@ -0,0 +1,524 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::collection::Owners;
+use crate::decks::DeckUsage;
+use crate::index::{normalize, split_quantity};
+use archidekt::{Entry, Owner};
+
+/// Copies of one card to borrow from an owner
+#[derive(Debug, Clone)]
+pub struct Borrowed {
+    pub name: String,
+    pub quantity: u32,
+    /// The set codes of the owner's printings, any of them will do
+    pub sets: Vec<String>,
+    /// `None` when none of the owner's printings has a price
+    pub value: Option<f32>,
+}
+
+impl Borrowed {
+    pub fn value_text(&self) -> String {
+        match self.value {
+            Some(value) => format!("{value:.2}€"),
+            None => "?".to_string(),
+        }
+    }
+}
+
+/// Everything to borrow from one owner
+#[derive(Debug, Clone)]
+pub struct Pull {
+    pub owner: Owner,
+    pub cards: Vec<Borrowed>,
+}
+
+/// Who to borrow a wantlist's cards from, bothering as few owners as possible
+#[derive(Debug, Clone, Default)]
+pub struct Plan {
+    pub pulls: Vec<Pull>,
+    /// Cards and how many copies of them no one has free
+    pub missing: Vec<(String, u32)>,
+}
+
+/// Above this many owners with something to lend, trying every combination of them takes too
+/// long and owners are picked greedily instead
+const EXHAUSTIVE_OWNERS: usize = 16;
+
+/// A card on the wantlist, with duplicate lines added up
+struct Want {
+    name: String,
+    quantity: u32,
+}
+
+/// What one owner can lend of one wanted card
+#[derive(Default, Clone)]
+struct Stock {
+    owned: u32,
+    /// Copies free across all printings, `None` when the card is in none of the owner's decks
+    free: Option<u32>,
+    /// The cheapest printing with a price, `None` when none of them has one
+    unit: Option<f32>,
+    sets: Vec<String>,
+}
+
+impl Stock {
+    fn available(&self) -> u32 {
+        self.free.map_or(self.owned, |free| free.min(self.owned))
+    }
+}
+
+impl Plan {
+    /// Plans borrowing the wantlist `list` from the owners `from` lets through
+    ///
+    /// Tries the combinations of the owners with something to lend from the fewest owners up,
+    /// which is fine for a pod, but falls back to picking them greedily above `EXHAUSTIVE_OWNERS`
+    pub fn new(list: &str, data: &[Entry], usage: &DeckUsage, from: &Owners) -> Self {
+        let mut wants: Vec<Want> = Vec::new();
+        let mut keys: HashMap<String, usize> = HashMap::new();
+        for (quantity, name) in list.lines().map(split_quantity) {
+            if name.is_empty() {
+                continue;
+            }
+            match keys.get(&normalize(name)) {
+                Some(&i) => wants[i].quantity += quantity,
+                None => {
+                    keys.insert(normalize(name), wants.len());
+                    wants.push(Want {
+                        name: name.to_string(),
+                        quantity,
+                    });
+                }
+            }
+        }
+
+        // Indexed by owner, then by want
+        let mut owners: Vec<Owner> = Vec::new();
+        let mut stock: Vec<Vec<Stock>> = Vec::new();
+        for entry in data {
+            if !from.test(&entry.owner) {
+                continue;
+            }
+            let name = normalize(&entry.name);
+            let front = name.split(" // ").next().unwrap_or_default();
+            let Some(&want) = keys.get(&name).or_else(|| keys.get(front)) else {
+                continue;
+            };
+
+            let owner = match owners.iter().position(|owner| *owner == entry.owner) {
+                Some(owner) => owner,
+                None => {
+                    owners.push(entry.owner.clone());
+                    stock.push(vec![Stock::default(); wants.len()]);
+                    owners.len() - 1
+                }
+            };
+            let card = &mut stock[owner][want];
+            if let Some(price) = entry.price {
+                card.unit = Some(card.unit.map_or(price, |unit| unit.min(price)));
+            }
+            card.owned += entry.quantity;
+            card.free = usage.get(entry).map(|usage| usage.free());
+            if !card.sets.contains(&entry.set) {
+                card.sets.push(entry.set.clone());
+            }
+        }
+
+        // Borrow what's there when no one has enough
+        let mut plan = Plan::default();
+        let needed: Vec<u32> = wants
+            .iter()
+            .enumerate()
+            .map(|(i, want)| {
+                let available: u32 = stock.iter().map(|stock| stock[i].available()).sum();
+                if available < want.quantity {
+                    plan.missing
+                        .push((want.name.clone(), want.quantity - available));
+                }
+                want.quantity.min(available)
+            })
+            .collect();
+
+        let lenders: Vec<usize> = (0..owners.len())
+            .filter(|&o| stock[o].iter().any(|card| card.available() > 0))
+            .collect();
+        let best = if lenders.len() <= EXHAUSTIVE_OWNERS {
+            exhaustive(&stock, &lenders, &needed)
+        } else {
+            greedy(&stock, &lenders, &needed)
+        };
+
+        let Some((_, taken)) = best else {
+            return plan;
+        };
+        for (o, owner) in owners.into_iter().enumerate() {
+            let cards: Vec<Borrowed> = wants
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| taken[o][*i] > 0)
+                .map(|(i, want)| Borrowed {
+                    name: want.name.clone(),
+                    quantity: taken[o][i],
+                    sets: stock[o][i].sets.clone(),
+                    value: stock[o][i].unit.map(|unit| unit * taken[o][i] as f32),
+                })
+                .collect();
+            if !cards.is_empty() {
+                plan.pulls.push(Pull { owner, cards });
+            }
+        }
+
+        plan
+    }
+
+    /// The total value of everything borrowed that has a price
+    pub fn value(&self) -> f32 {
+        self.pulls
+            .iter()
+            .flat_map(|pull| &pull.cards)
+            .filter_map(|card| card.value)
+            .fold(0.0, |sum, value| sum + value)
+    }
+
+    /// Whether every borrowed card has a price, otherwise `value` leaves some out
+    pub fn is_priced(&self) -> bool {
+        self.pulls
+            .iter()
+            .flat_map(|pull| &pull.cards)
+            .all(|card| card.value.is_some())
+    }
+
+    /// The total value, with a `?` when some cards have no price
+    pub fn value_text(&self) -> String {
+        if self.is_priced() {
+            format!("{:.2}€", self.value())
+        } else {
+            format!("{:.2}€ + ?", self.value())
+        }
+    }
+
+    /// One pull list per owner as plain text, e.g. to send to them
+    pub fn to_text(&self) -> String {
+        let mut text = String::new();
+        for pull in &self.pulls {
+            let _ = writeln!(text, "From {}:", pull.owner);
+            for card in &pull.cards {
+                let _ = writeln!(
+                    text,
+                    "  {} {} ({})",
+                    card.quantity,
+                    card.name,
+                    card.sets.join(", ")
+                );
+            }
+        }
+        if !self.missing.is_empty() {
+            let _ = writeln!(text, "No one has:");
+            for (name, quantity) in &self.missing {
+                let _ = writeln!(text, "  {quantity} {name}");
+            }
+        }
+        text
+    }
+}
+
+/// Tries the combinations of `lenders` by how many owners they bother, stopping at the first
+/// count that covers the wantlist and taking its cheapest combination
+fn exhaustive(stock: &[Vec<Stock>], lenders: &[usize], needed: &[u32]) -> Option<Allocation> {
+    (0..=lenders.len()).find_map(|count| {
+        masks(lenders.len(), count)
+            .filter_map(|mask| {
+                let chosen: Vec<usize> = (0..lenders.len())
+                    .filter(|l| mask & 1 << l != 0)
+                    .map(|l| lenders[l])
+                    .collect();
+                allocate(stock, &chosen, needed)
+            })
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+    })
+}
+
+/// Every mask of the `n` lowest bits with `count` of them set, in increasing order
+fn masks(n: usize, count: usize) -> impl Iterator<Item = u32> {
+    let first = (1u32 << count) - 1;
+    std::iter::successors(Some(first), move |&mask| {
+        if mask == 0 {
+            return None;
+        }
+        // Gosper's hack, the next larger number with as many bits set
+        let lowest = mask & mask.wrapping_neg();
+        let carried = mask + lowest;
+        Some((((carried ^ mask) >> 2) / lowest) | carried)
+    })
+    .take_while(move |&mask| mask < 1 << n)
+}
+
+/// Adds whichever of `lenders` covers the most still needed copies until they're all covered
+fn greedy(stock: &[Vec<Stock>], lenders: &[usize], needed: &[u32]) -> Option<Allocation> {
+    let mut chosen: Vec<usize> = Vec::new();
+    let mut left = needed.to_vec();
+    loop {
+        if let Some(found) = allocate(stock, &chosen, needed) {
+            return Some(found);
+        }
+        let covers = |o: usize| -> u32 {
+            left.iter()
+                .enumerate()
+                .map(|(want, left)| stock[o][want].available().min(*left))
+                .sum()
+        };
+        let next = lenders
+            .iter()
+            .copied()
+            .filter(|o| !chosen.contains(o))
+            .max_by_key(|&o| covers(o))
+            .filter(|&o| covers(o) > 0)?;
+        for (want, left) in left.iter_mut().enumerate() {
+            *left -= stock[next][want].available().min(*left);
+        }
+        chosen.push(next);
+    }
+}
+
+/// What borrowing some copies costs, unpriced copies counting as worth more than any price
+#[derive(Debug, Clone, Copy, Default)]
+struct Cost {
+    unpriced: u32,
+    value: f32,
+}
+
+impl Cost {
+    fn total_cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.unpriced
+            .cmp(&other.unpriced)
+            .then(self.value.total_cmp(&other.value))
+    }
+}
+
+/// The cost and the copies taken per owner and want
+type Allocation = (Cost, Vec<Vec<u32>>);
+
+/// The cheapest way to get `needed` copies of each want from the `chosen` owners, taking unpriced
+/// copies last, `None` if they don't have enough
+fn allocate(stock: &[Vec<Stock>], chosen: &[usize], needed: &[u32]) -> Option<Allocation> {
+    let mut taken = vec![vec![0; needed.len()]; stock.len()];
+    let mut cost = Cost::default();
+
+    for (want, &needed) in needed.iter().enumerate() {
+        let mut owners: Vec<usize> = chosen
+            .iter()
+            .copied()
+            .filter(|&o| stock[o][want].available() > 0)
+            .collect();
+        let unit = |o: usize| stock[o][want].unit.unwrap_or(f32::INFINITY);
+        owners.sort_by(|&a, &b| unit(a).total_cmp(&unit(b)));
+
+        let mut left = needed;
+        for o in owners {
+            let take = left.min(stock[o][want].available());
+            taken[o][want] = take;
+            match stock[o][want].unit {
+                Some(unit) => cost.value += unit * take as f32,
+                None => cost.unpriced += take,
+            }
+            left -= take;
+            if left == 0 {
+                break;
+            }
+        }
+        if left > 0 {
+            return None;
+        }
+    }
+
+    Some((cost, taken))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use archidekt::{ColorIdent, Deck, DeckCard, User};
+
+    fn entry(owner: Owner, name: &str, quantity: u32, price: impl Into<Option<f32>>) -> Entry {
+        Entry {
+            owner,
+            quantity,
+            color_identity: ColorIdent::new(),
+            name: name.into(),
+            ty: String::new(),
+            set: "set".into(),
+            scryfall: String::new(),
+            price: price.into(),
+        }
+    }
+
+    fn member(user: User, name: &str, quantity: u32, price: impl Into<Option<f32>>) -> Entry {
+        entry(user.into(), name, quantity, price)
+    }
+
+    fn plan(list: &str, data: &[Entry], decks: &[Deck]) -> Plan {
+        Plan::new(list, data, &DeckUsage::new(data, decks), &Owners::default())
+    }
+
+    fn pulled(plan: &Plan) -> Vec<(String, String, u32)> {
+        plan.pulls
+            .iter()
+            .flat_map(|pull| {
+                pull.cards
+                    .iter()
+                    .map(|card| (pull.owner.to_string(), card.name.clone(), card.quantity))
+            })
+            .collect()
+    }
+
+    fn stock(owned: u32, free: Option<u32>, unit: impl Into<Option<f32>>) -> Stock {
+        Stock {
+            owned,
+            free,
+            unit: unit.into(),
+            sets: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn allocate_takes_the_cheapest_copies() {
+        let stock = [vec![stock(2, None, 5.0)], vec![stock(2, None, 1.0)]];
+
+        let (cost, taken) = allocate(&stock, &[0, 1], &[3]).unwrap();
+        assert_eq!(taken, [[1], [2]]);
+        assert_eq!(cost.value, 7.0);
+    }
+
+    #[test]
+    fn allocate_takes_unpriced_copies_last() {
+        let stock = [vec![stock(2, None, None)], vec![stock(2, None, 50.0)]];
+
+        let (cost, taken) = allocate(&stock, &[0, 1], &[3]).unwrap();
+        assert_eq!(taken, [[1], [2]]);
+        assert_eq!((cost.unpriced, cost.value), (1, 100.0));
+    }
+
+    #[test]
+    fn masks_count_up_by_bits_set() {
+        assert_eq!(masks(3, 0).collect::<Vec<_>>(), [0]);
+        assert_eq!(masks(4, 2).collect::<Vec<_>>(), [3, 5, 6, 9, 10, 12]);
+        assert_eq!(masks(3, 3).collect::<Vec<_>>(), [7]);
+        assert_eq!(masks(16, 16).count(), 1);
+    }
+
+    #[test]
+    fn allocate_only_lends_free_copies() {
+        let stock = [vec![stock(3, Some(1), 1.0)]];
+
+        assert!(allocate(&stock, &[0], &[2]).is_none());
+        assert_eq!(allocate(&stock, &[0], &[1]).unwrap().1, [[1]]);
+    }
+
+    #[test]
+    fn adds_up_quantities() {
+        let data = [member(User::Strosel, "Sol Ring", 4, 1.0)];
+        let plan = plan("2x Sol Ring\n1 sol ring", &data, &[]);
+
+        assert_eq!(pulled(&plan), [("Strosel".into(), "Sol Ring".into(), 3)]);
+        assert!(plan.missing.is_empty());
+    }
+
+    #[test]
+    fn finds_double_faced_cards_by_their_front() {
+        let data = [member(User::Strosel, "Fire // Ice", 1, 1.0)];
+        let plan = plan("1 fire\n\n", &data, &[]);
+
+        assert_eq!(pulled(&plan), [("Strosel".into(), "fire".into(), 1)]);
+        assert!(plan.missing.is_empty());
+    }
+
+    #[test]
+    fn leaves_deck_copies_alone() {
+        let data = [member(User::Strosel, "Sol Ring", 2, 1.0)];
+        let deck = Deck {
+            id: 1,
+            name: "Artifacts".into(),
+            owner: User::Strosel,
+            cards: vec![DeckCard {
+                quantity: 1,
+                name: "Sol Ring".into(),
+                set: String::new(),
+                scryfall: String::new(),
+            }],
+        };
+        let plan = plan("2 Sol Ring", &data, &[deck]);
+
+        assert_eq!(pulled(&plan), [("Strosel".into(), "Sol Ring".into(), 1)]);
+        assert_eq!(plan.missing, [("Sol Ring".into(), 1)]);
+    }
+
+    #[test]
+    fn bothers_as_few_owners_as_possible() {
+        let data = [
+            member(User::Strosel, "Sol Ring", 1, 1.0),
+            member(User::VikinGG, "Mind Stone", 1, 1.0),
+            member(User::Amon8808, "Sol Ring", 1, 9.0),
+            member(User::Amon8808, "Mind Stone", 1, 9.0),
+        ];
+        let plan = plan("Sol Ring\nMind Stone", &data, &[]);
+
+        assert_eq!(plan.pulls.len(), 1);
+        assert_eq!(plan.pulls[0].owner, Owner::from(User::Amon8808));
+        assert_eq!(plan.value(), 18.0);
+    }
+
+    #[test]
+    fn then_borrows_the_least_value() {
+        let data = [
+            member(User::Strosel, "Sol Ring", 1, 5.0),
+            member(User::VikinGG, "Sol Ring", 1, 2.0),
+        ];
+        let plan = plan("Sol Ring", &data, &[]);
+
+        assert_eq!(pulled(&plan), [("VikinGG".into(), "Sol Ring".into(), 1)]);
+    }
+
+    #[test]
+    fn unpriced_copies_arent_free() {
+        let data = [
+            member(User::Strosel, "Sol Ring", 1, None),
+            member(User::VikinGG, "Sol Ring", 1, 9.0),
+        ];
+        let plan = plan("Sol Ring", &data, &[]);
+
+        assert_eq!(pulled(&plan), [("VikinGG".into(), "Sol Ring".into(), 1)]);
+        assert_eq!(plan.value_text(), "9.00€");
+    }
+
+    #[test]
+    fn unpriced_copies_make_the_value_unknown() {
+        let data = [
+            member(User::Strosel, "Sol Ring", 1, None),
+            member(User::Strosel, "Mind Stone", 1, 2.0),
+        ];
+        let plan = plan("Sol Ring\nMind Stone", &data, &[]);
+
+        assert!(!plan.is_priced());
+        assert_eq!(plan.value(), 2.0);
+        assert_eq!(plan.value_text(), "2.00€ + ?");
+    }
+
+    #[test]
+    fn many_guests_fall_back_to_greedy() {
+        let mut data: Vec<Entry> = (0..40)
+            .map(|n| entry(Owner::Guest(format!("Guest {n}")), "Sol Ring", 1, 1.0))
+            .collect();
+        data.push(entry(Owner::Guest("Hoarder".into()), "Sol Ring", 5, 1.0));
+        let plan = plan("6 Sol Ring", &data, &[]);
+
+        assert_eq!(plan.pulls.len(), 2);
+        assert_eq!(
+            plan.pulls
+                .iter()
+                .map(|pull| pull.cards[0].quantity)
+                .sum::<u32>(),
+            6
+        );
+        assert!(plan.missing.is_empty());
+    }
+}
@@ -0,0 +1,77 @@
+use crate::collection::Search;
+
+/// Saved searches are stored on their own so a search that no longer reads only loses itself
+const STORAGE_KEY: &str = "saved_searches";
+
+/// A named search to recall later, e.g. "Sultai under 1€"
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct SavedSearch {
+    pub name: String,
+    pub search: Search,
+    /// Whether printings are grouped
+    pub dedup: bool,
+}
+
+/// The state of the saved searches window
+#[derive(Debug, Default)]
+pub struct SavedWindow {
+    /// Name for saving the current search
+    pub name: String,
+    /// JSON pasted to be imported
+    pub json: String,
+    pub error: Option<String>,
+}
+
+/// Adds `search`, replacing any saved search with the same name
+pub fn save(saved: &mut Vec<SavedSearch>, search: SavedSearch) {
+    match saved.iter_mut().find(|other| other.name == search.name) {
+        Some(other) => *other = search,
+        None => saved.push(search),
+    }
+}
+
+pub fn export(saved: &[SavedSearch]) -> String {
+    serde_json::to_string_pretty(saved).unwrap_or_default()
+}
+
+/// Reads a JSON list of saved searches, skipping those `Search` has changed too much to read
+fn parse(json: &str) -> anyhow::Result<Vec<SavedSearch>> {
+    let values: Vec<serde_json::Value> = serde_json::from_str(json)?;
+    Ok(values
+        .into_iter()
+        .filter_map(|value| {
+            serde_json::from_value(value)
+                .inspect_err(|e| log::warn!("Skipping saved search: {e}"))
+                .ok()
+        })
+        .collect())
+}
+
+/// Where saved searches were kept before they got a key of their own
+#[derive(serde::Deserialize)]
+struct Legacy {
+    saved: Vec<SavedSearch>,
+}
+
+pub fn load(storage: &dyn eframe::Storage) -> Vec<SavedSearch> {
+    match storage.get_string(STORAGE_KEY) {
+        Some(json) => parse(&json).unwrap_or_default(),
+        None => eframe::get_value::<Legacy>(storage, eframe::APP_KEY)
+            .map(|legacy| legacy.saved)
+            .unwrap_or_default(),
+    }
+}
+
+pub fn store(storage: &mut dyn eframe::Storage, saved: &[SavedSearch]) {
+    storage.set_string(STORAGE_KEY, export(saved));
+}
+
+/// Merges the searches exported by someone else into `saved`, returning how many there were
+pub fn import(saved: &mut Vec<SavedSearch>, json: &str) -> anyhow::Result<usize> {
+    let imported = parse(json)?;
+    let count = imported.len();
+    for search in imported {
+        save(saved, search);
+    }
+    Ok(count)
+}
@@ -0,0 +1,343 @@
+//! The search encoded in the URL fragment, so a search can be shared as a link
+use crate::collection::{ColorCount, ColorMatch, Owners, Search};
+use archidekt::{ColorIdent, Owner, User};
+use strum::VariantArray;
+
+fn match_key(mode: ColorMatch) -> &'static str {
+    match mode {
+        ColorMatch::Exactly => "exactly",
+        ColorMatch::AtMost => "atmost",
+        ColorMatch::AtLeast => "atleast",
+        ColorMatch::Includes => "includes",
+    }
+}
+
+fn count_key(count: ColorCount) -> &'static str {
+    match count {
+        ColorCount::Any => "any",
+        ColorCount::Mono => "mono",
+        ColorCount::Two => "two",
+        ColorCount::ThreePlus => "multi",
+    }
+}
+
+/// Adds the owner filter with one `owner` or `exclude` pair per owner, since guest names can
+/// hold any character
+fn push_owners(pairs: &mut Vec<(&str, String)>, owners: &Owners) {
+    for (key, list) in [("owner", &owners.include), ("exclude", &owners.exclude)] {
+        pairs.extend(list.iter().map(|owner| (key, owner.to_string())));
+    }
+}
+
+/// Encodes a search as `key=value` pairs, leaving out everything at its default
+pub fn to_fragment(search: &Search, dedup: bool) -> String {
+    let mut pairs: Vec<(&str, String)> = Vec::new();
+
+    match search {
+        Search::Single {
+            owners,
+            color,
+            colorless,
+            color_match,
+            color_count,
+            name,
+            ty,
+            set,
+        } => {
+            pairs.push(("mode", "simple".into()));
+            push_owners(&mut pairs, owners);
+            if !color.is_colorless() {
+                pairs.push(("colors", color.to_string()));
+            }
+            if *colorless {
+                pairs.push(("colorless", "1".into()));
+            }
+            if *color_match != ColorMatch::default() {
+                pairs.push(("match", match_key(*color_match).into()));
+            }
+            if *color_count != ColorCount::default() {
+                pairs.push(("count", count_key(*color_count).into()));
+            }
+            for (key, text) in [("name", name), ("type", ty), ("set", set)] {
+                if !text.is_empty() {
+                    pairs.push((key, text.clone()));
+                }
+            }
+        }
+        Search::Wantlist { list, owners } => {
+            pairs.push(("mode", "wantlist".into()));
+            push_owners(&mut pairs, owners);
+            if !list.is_empty() {
+                pairs.push(("list", list.clone()));
+            }
+        }
+    }
+
+    if dedup {
+        pairs.push(("dedup", "1".into()));
+    }
+
+    pairs
+        .into_iter()
+        .map(|(key, value)| format!("{key}={}", encode(&value)))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+/// Decodes a fragment written by `to_fragment`, unknown keys and values are ignored
+pub fn from_fragment(fragment: &str) -> Option<(Search, bool)> {
+    let pairs: Vec<(&str, String)> = fragment
+        .trim_start_matches('#')
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(key, value)| (key, decode(value)))
+        .collect();
+    let get = |key: &str| {
+        pairs
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, value)| value.as_str())
+    };
+    // Names that aren't members are guests, who only mean something to whoever imported them
+    let owners = |key: &str| -> Vec<Owner> {
+        pairs
+            .iter()
+            .filter(|(k, name)| *k == key && !name.is_empty())
+            .map(|(_, name)| {
+                User::VARIANTS
+                    .iter()
+                    .find(|user| user.to_string() == *name)
+                    .map_or_else(|| Owner::Guest(name.to_string()), |user| (*user).into())
+            })
+            .collect()
+    };
+    let owners = Owners {
+        include: owners("owner"),
+        exclude: owners("exclude"),
+    };
+    let dedup = get("dedup") == Some("1");
+
+    let search = match get("mode")? {
+        "simple" => Search::Single {
+            owners,
+            color: get("colors")
+                .and_then(|colors| colors.parse().ok())
+                .unwrap_or_else(ColorIdent::new),
+            colorless: get("colorless") == Some("1"),
+            color_match: get("match")
+                .and_then(|key| {
+                    ColorMatch::ALL
+                        .into_iter()
+                        .find(|mode| match_key(*mode) == key)
+                })
+                .unwrap_or_default(),
+            color_count: get("count")
+                .and_then(|key| {
+                    ColorCount::ALL
+                        .into_iter()
+                        .find(|count| count_key(*count) == key)
+                })
+                .unwrap_or_default(),
+            name: get("name").unwrap_or_default().to_string(),
+            ty: get("type").unwrap_or_default().to_string(),
+            set: get("set").unwrap_or_default().to_string(),
+        },
+        "wantlist" => Search::Wantlist {
+            list: get("list").unwrap_or_default().to_string(),
+            owners,
+        },
+        _ => return None,
+    };
+
+    Some((search, dedup))
+}
+
+/// Percent-encodes everything but unreserved characters
+fn encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+fn decode(value: &str) -> String {
+    let mut bytes = Vec::with_capacity(value.len());
+    let mut rest = value.as_bytes();
+    while let Some((&b, tail)) = rest.split_first() {
+        rest = tail;
+        match b {
+            b'+' => bytes.push(b' '),
+            b'%' => match rest
+                .get(..2)
+                .and_then(|hex| std::str::from_utf8(hex).ok())
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+            {
+                Some(decoded) => {
+                    bytes.push(decoded);
+                    rest = &rest[2..];
+                }
+                None => bytes.push(b),
+            },
+            _ => bytes.push(b),
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// The fragment of the page's URL, without the `#`
+#[cfg(target_arch = "wasm32")]
+pub fn fragment() -> String {
+    web_sys::window()
+        .and_then(|window| window.location().hash().ok())
+        .unwrap_or_default()
+        .trim_start_matches('#')
+        .to_string()
+}
+
+/// Replaces the fragment in the address bar without adding a history entry
+#[cfg(target_arch = "wasm32")]
+pub fn set_fragment(fragment: &str) {
+    let Some(history) = web_sys::window().and_then(|window| window.history().ok()) else {
+        return;
+    };
+    if let Err(e) = history.replace_state_with_url(
+        &wasm_bindgen::JsValue::NULL,
+        "",
+        Some(&format!("#{fragment}")),
+    ) {
+        log::warn!("Failed updating the address bar: {e:?}");
+    }
+}
+
+/// The page's URL with `fragment`
+#[cfg(target_arch = "wasm32")]
+pub fn link(fragment: &str) -> String {
+    let href = web_sys::window()
+        .and_then(|window| window.location().href().ok())
+        .unwrap_or_default();
+    let page = href.split_once('#').map_or(href.as_str(), |(page, _)| page);
+    format!("{page}#{fragment}")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(search: Search, dedup: bool) {
+        let fragment = to_fragment(&search, dedup);
+        assert_eq!(
+            from_fragment(&fragment),
+            Some((search, dedup)),
+            "{fragment}"
+        );
+    }
+
+    fn owners() -> Owners {
+        Owners {
+            include: vec![User::Strosel.into(), Owner::Guest("Smith, J".into())],
+            exclude: vec![Owner::Guest("50% & more=#".into())],
+        }
+    }
+
+    #[test]
+    fn single_round_trips() {
+        round_trip(
+            Search::Single {
+                owners: owners(),
+                color: "UBG".parse().unwrap(),
+                colorless: true,
+                color_match: ColorMatch::AtLeast,
+                color_count: ColorCount::ThreePlus,
+                name: "Sol Ring & co".into(),
+                ty: "Légendary Creature".into(),
+                set: "c21".into(),
+            },
+            true,
+        );
+        round_trip(
+            Search::Single {
+                owners: Owners::default(),
+                color: ColorIdent::new(),
+                colorless: false,
+                color_match: ColorMatch::default(),
+                color_count: ColorCount::default(),
+                name: String::new(),
+                ty: String::new(),
+                set: String::new(),
+            },
+            false,
+        );
+    }
+
+    #[test]
+    fn wantlist_round_trips() {
+        round_trip(
+            Search::Wantlist {
+                list: "2x Sol Ring\n1 Fire // Ice\n# comment".into(),
+                owners: owners(),
+            },
+            false,
+        );
+        round_trip(
+            Search::Wantlist {
+                list: String::new(),
+                owners: Owners::default(),
+            },
+            true,
+        );
+    }
+
+    #[test]
+    fn names_are_members_or_guests() {
+        let (search, _) = from_fragment("mode=wantlist&owner=Strosel&owner=Bob").unwrap();
+        let Search::Wantlist { owners, .. } = search else {
+            panic!("not a wantlist");
+        };
+        assert_eq!(
+            owners.include,
+            [User::Strosel.into(), Owner::Guest("Bob".into())]
+        );
+    }
+
+    #[test]
+    fn ignores_malformed_fragments() {
+        assert_eq!(from_fragment(""), None);
+        assert_eq!(from_fragment("#"), None);
+        assert_eq!(from_fragment("name=Sol"), None);
+        assert_eq!(from_fragment("mode=unknown"), None);
+
+        let (search, dedup) =
+            from_fragment("#mode=simple&&junk&colors=purple&match=most&count=9&name=%E&dedup=yes")
+                .unwrap();
+        assert!(!dedup);
+        let Search::Single {
+            color,
+            color_match,
+            color_count,
+            name,
+            ..
+        } = search
+        else {
+            panic!("not a simple search");
+        };
+        assert!(color.is_colorless());
+        assert_eq!(color_match, ColorMatch::default());
+        assert_eq!(color_count, ColorCount::default());
+        assert_eq!(name, "%E");
+    }
+
+    #[test]
+    fn decodes_what_it_encodes() {
+        for text in ["", "plain", "a b+c", "100%", "å // ø", "%zz", "a=b&c#d"] {
+            assert_eq!(decode(&encode(text)), text);
+        }
+        assert_eq!(decode("a+b%20c"), "a b c");
+        assert_eq!(decode("%"), "%");
+        assert_eq!(decode("%F0%9F"), "\u{FFFD}");
+    }
+}
@@ -0,0 +1,110 @@
+use archidekt::{Entry, Owner};
+use std::collections::{BTreeMap, HashMap};
+
+/// Supertypes are folded into `Entry::ty` but aren't interesting as a breakdown
+const SUPERTYPES: [&str; 5] = ["legendary", "basic", "snow", "world", "ongoing"];
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Tally {
+    pub cards: u32,
+    pub value: f32,
+}
+
+impl Tally {
+    fn add(&mut self, entry: &Entry) {
+        self.cards += entry.quantity;
+        if let Some(price) = entry.price {
+            self.value += price * entry.quantity as f32;
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Stats {
+    pub total: Tally,
+    pub owners: Vec<(Owner, Tally)>,
+    /// Cards per color in "WUBRGC" order, multicolored cards count towards each of their colors
+    pub colors: [u32; 6],
+    pub types: Vec<(String, u32)>,
+    pub sets: Vec<(String, u32)>,
+    pub valuable: Vec<Entry>,
+    pub duplicated: Vec<(String, u32)>,
+}
+
+impl Stats {
+    pub const TOP: usize = 10;
+
+    pub fn new<'a>(entries: impl Iterator<Item = &'a Entry>) -> Self {
+        let mut stats = Self::default();
+        let mut valuable: Vec<&Entry> = Vec::new();
+        let mut owners: BTreeMap<Owner, Tally> = BTreeMap::new();
+        let mut types: HashMap<String, u32> = HashMap::new();
+        let mut sets: HashMap<&str, u32> = HashMap::new();
+        let mut names: HashMap<&str, u32> = HashMap::new();
+
+        for entry in entries {
+            let quantity = entry.quantity;
+            stats.total.add(entry);
+            owners.entry(entry.owner.clone()).or_default().add(entry);
+
+            if entry.color_identity.iter().any(|c| *c) {
+                for (count, _) in stats
+                    .colors
+                    .iter_mut()
+                    .zip(entry.color_identity.iter())
+                    .filter(|(_, c)| **c)
+                {
+                    *count += quantity;
+                }
+            } else {
+                stats.colors[5] += quantity;
+            }
+
+            for ty in entry.ty.split(',').map(str::trim) {
+                if !ty.is_empty() && !SUPERTYPES.contains(&ty.to_lowercase().as_str()) {
+                    *types.entry(ty.to_string()).or_default() += quantity;
+                }
+            }
+
+            if !entry.set.is_empty() {
+                *sets.entry(&entry.set).or_default() += quantity;
+            }
+            *names.entry(&entry.name).or_default() += quantity;
+
+            if entry.price.is_some() {
+                valuable.push(entry);
+            }
+        }
+
+        stats.owners = owners.into_iter().collect();
+
+        stats.types = top(types.into_iter(), usize::MAX);
+        stats.sets = top(
+            sets.into_iter().map(|(set, n)| (set.to_uppercase(), n)),
+            Self::TOP,
+        );
+        stats.duplicated = top(
+            names
+                .into_iter()
+                .filter(|(_, n)| *n > 1)
+                .map(|(name, n)| (name.to_string(), n)),
+            Self::TOP,
+        );
+
+        valuable.sort_by(|a, b| {
+            b.price
+                .unwrap_or_default()
+                .total_cmp(&a.price.unwrap_or_default())
+        });
+        stats.valuable = valuable.into_iter().take(Self::TOP).cloned().collect();
+
+        stats
+    }
+}
+
+fn top<K: Ord>(counts: impl Iterator<Item = (K, u32)>, n: usize) -> Vec<(K, u32)> {
+    let mut counts: Vec<_> = counts.collect();
+    counts.sort_by(|(ka, a), (kb, b)| b.cmp(a).then(ka.cmp(kb)));
+    counts.truncate(n);
+    counts
+}
@@ -0,0 +1,48 @@
+//! Copies of the last loaded snapshot in the browser's Cache API, so the app starts offline
+use anyhow::anyhow;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+
+/// Kept apart from the service worker's cache of the app itself
+const CACHE_NAME: &str = "mtg-searcher-data";
+
+fn js_error(e: JsValue) -> anyhow::Error {
+    anyhow!("{e:?}")
+}
+
+async fn cache() -> anyhow::Result<web_sys::Cache> {
+    let caches = web_sys::window()
+        .ok_or_else(|| anyhow!("No window"))?
+        .caches()
+        .map_err(js_error)?;
+    let cache = JsFuture::from(caches.open(CACHE_NAME))
+        .await
+        .map_err(js_error)?;
+    Ok(cache.unchecked_into())
+}
+
+/// The stored copy of `path`, if any
+pub async fn load(path: &str) -> anyhow::Result<Option<Vec<u8>>> {
+    let found = JsFuture::from(cache().await?.match_with_str(path))
+        .await
+        .map_err(js_error)?;
+    if found.is_undefined() {
+        return Ok(None);
+    }
+
+    let resp: web_sys::Response = found.unchecked_into();
+    let buffer = JsFuture::from(resp.array_buffer().map_err(js_error)?)
+        .await
+        .map_err(js_error)?;
+    Ok(Some(js_sys::Uint8Array::new(&buffer).to_vec()))
+}
+
+/// Replaces the stored copy of `path`
+pub async fn store(path: &str, mut bytes: Vec<u8>) -> anyhow::Result<()> {
+    let resp =
+        web_sys::Response::new_with_opt_u8_array(Some(bytes.as_mut_slice())).map_err(js_error)?;
+    JsFuture::from(cache().await?.put_with_str(path, &resp))
+        .await
+        .map_err(js_error)?;
+    Ok(())
+}
@@ -0,0 +1,317 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+
+use crate::decks::DeckUsage;
+use crate::index::normalize;
+use crate::wants::Match;
+use archidekt::{Entry, Owner, User};
+
+/// The cards one member hands to the next in a trade
+#[derive(Debug, Clone)]
+pub struct Leg {
+    pub from: User,
+    pub to: User,
+    /// Indices into the collection, one printing per wanted card
+    pub cards: Vec<usize>,
+    /// The value of the cards with a price
+    pub value: f32,
+    /// How many of the cards have no price
+    pub unpriced: usize,
+}
+
+impl Leg {
+    /// The value, with a `?` when some cards have no price
+    pub fn value_text(&self) -> String {
+        match self.unpriced {
+            0 => format!("{:.2}€", self.value),
+            _ => format!("{:.2}€ + ?", self.value),
+        }
+    }
+}
+
+/// Members handing cards around in a circle, each getting something they want
+#[derive(Debug, Clone)]
+pub struct Trade {
+    pub legs: Vec<Leg>,
+}
+
+impl Trade {
+    /// Whether every card in the trade has a price, otherwise the imbalance is a guess
+    pub fn is_priced(&self) -> bool {
+        self.legs.iter().all(|leg| leg.unpriced == 0)
+    }
+
+    /// How far apart the legs are in value, e.g. `12% apart in value`
+    pub fn balance_text(&self) -> String {
+        if self.is_priced() {
+            format!("{:.0}% apart in value", 100.0 * self.imbalance())
+        } else {
+            "? apart in value".to_string()
+        }
+    }
+
+    /// How far apart the most and least valuable legs are, relative to the most valuable
+    pub fn imbalance(&self) -> f32 {
+        let (min, max) = self
+            .legs
+            .iter()
+            .fold((f32::MAX, 0.0f32), |(min, max), leg| {
+                (min.min(leg.value), max.max(leg.value))
+            });
+        if max > 0.0 {
+            (max - min) / max
+        } else {
+            0.0
+        }
+    }
+
+    /// Who trades, like `Strosel → VikinGG → Strosel`
+    pub fn title(&self) -> String {
+        let mut title: Vec<String> = self.legs.iter().map(|leg| leg.from.to_string()).collect();
+        if let Some(first) = self.legs.first() {
+            title.push(first.from.to_string());
+        }
+        title.join(" → ")
+    }
+}
+
+/// Pairs and three-way circles of members where everyone has something the next one wants,
+/// most balanced first and those with unpriced cards last
+///
+/// Each leg offers one free copy of every wanted card, the cheapest printing when there are
+/// several and an unpriced one only when no printing has a price
+pub fn find(data: &[Entry], matches: &[Match], usage: &DeckUsage) -> Vec<Trade> {
+    let mut legs: BTreeMap<(User, User), Leg> = BTreeMap::new();
+    for Match {
+        owner,
+        wanter,
+        cards,
+    } in matches
+    {
+        let Owner::Member(from) = owner else {
+            continue;
+        };
+
+        let mut cheapest: HashMap<String, usize> = HashMap::new();
+        for &i in cards.iter().filter(|&&i| usage.free(&data[i]) > 0) {
+            // Unpriced printings sort last
+            let price = |i: usize| data[i].price.unwrap_or(f32::INFINITY);
+            cheapest
+                .entry(normalize(&data[i].name))
+                .and_modify(|other| {
+                    if price(i) < price(*other) {
+                        *other = i;
+                    }
+                })
+                .or_insert(i);
+        }
+        if cheapest.is_empty() {
+            continue;
+        }
+
+        let mut cards: Vec<usize> = cheapest.into_values().collect();
+        cards.sort_by(|a, b| data[*a].name.cmp(&data[*b].name));
+        // Summing no prices at all gives -0.0
+        let value = cards
+            .iter()
+            .filter_map(|&i| data[i].price)
+            .fold(0.0, |sum, price| sum + price);
+        let unpriced = cards.iter().filter(|&&i| data[i].price.is_none()).count();
+        legs.insert(
+            (*from, *wanter),
+            Leg {
+                from: *from,
+                to: *wanter,
+                cards,
+                value,
+                unpriced,
+            },
+        );
+    }
+
+    let mut members: Vec<User> = legs.keys().map(|(from, _)| *from).collect();
+    members.dedup();
+    let leg = |from: User, to: User| legs.get(&(from, to)).cloned();
+    let mut trades = Vec::new();
+
+    // Each circle is found once, starting from its smallest member
+    for &a in &members {
+        for &b in members.iter().filter(|&&b| b > a) {
+            if let (Some(ab), Some(ba)) = (leg(a, b), leg(b, a)) {
+                trades.push(Trade { legs: vec![ab, ba] });
+            }
+            for &c in members.iter().filter(|&&c| c > a && c != b) {
+                if let (Some(ab), Some(bc), Some(ca)) = (leg(a, b), leg(b, c), leg(c, a)) {
+                    trades.push(Trade {
+                        legs: vec![ab, bc, ca],
+                    });
+                }
+            }
+        }
+    }
+
+    trades.sort_by(|a, b| {
+        (!a.is_priced())
+            .cmp(&!b.is_priced())
+            .then(a.imbalance().total_cmp(&b.imbalance()))
+    });
+    trades
+}
+
+/// The suggested trades as plain text, e.g. to paste in the group chat
+pub fn export(data: &[Entry], trades: &[Trade]) -> String {
+    let mut text = String::new();
+    for trade in trades {
+        let _ = writeln!(text, "{}", trade.title());
+        for leg in &trade.legs {
+            let _ = writeln!(
+                text,
+                "  {} gives {} ({}):",
+                leg.from,
+                leg.to,
+                leg.value_text()
+            );
+            for &i in &leg.cards {
+                let entry = &data[i];
+                let price = entry.price.map_or(String::new(), |p| format!(", {p:.2}€"));
+                let _ = writeln!(text, "    {} ({}{price})", entry.name, entry.set);
+            }
+        }
+        text.push('\n');
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::wants::Wants;
+    use archidekt::{ColorIdent, Deck, DeckCard};
+
+    const S: User = User::Strosel;
+    const V: User = User::VikinGG;
+    const A: User = User::Amon8808;
+
+    fn entry(owner: User, name: &str, price: Option<f32>) -> Entry {
+        Entry {
+            owner: owner.into(),
+            quantity: 1,
+            color_identity: ColorIdent::new(),
+            name: name.into(),
+            ty: String::new(),
+            set: "set".into(),
+            scryfall: format!("{owner}-{name}-{price:?}"),
+            price,
+        }
+    }
+
+    fn trades(data: &[Entry], lists: &[(User, &str)], decks: &[Deck]) -> Vec<Trade> {
+        let mut wants = Wants::new(
+            lists
+                .iter()
+                .map(|(user, list)| (*user, list.to_string()))
+                .collect(),
+        );
+        wants.index(data);
+        find(data, &wants.matches(data), &DeckUsage::new(data, decks))
+    }
+
+    fn members(trade: &Trade) -> Vec<(User, User)> {
+        trade.legs.iter().map(|leg| (leg.from, leg.to)).collect()
+    }
+
+    #[test]
+    fn pairs() {
+        let data = [
+            entry(S, "Sol Ring", Some(2.0)),
+            entry(V, "Mana Crypt", Some(150.0)),
+        ];
+        let trades = trades(&data, &[(S, "Mana Crypt"), (V, "Sol Ring")], &[]);
+
+        assert_eq!(trades.len(), 1);
+        assert_eq!(members(&trades[0]), [(S, V), (V, S)]);
+        assert_eq!(trades[0].balance_text(), "99% apart in value");
+    }
+
+    #[test]
+    fn three_way_circles() {
+        let data = [
+            entry(S, "Sol Ring", Some(2.0)),
+            entry(V, "Arcane Signet", Some(1.0)),
+            entry(A, "Mind Stone", Some(0.5)),
+        ];
+        let lists = [(V, "Sol Ring"), (A, "Arcane Signet"), (S, "Mind Stone")];
+        let trades = trades(&data, &lists, &[]);
+
+        assert_eq!(trades.len(), 1);
+        assert_eq!(members(&trades[0]), [(A, S), (S, V), (V, A)]);
+    }
+
+    #[test]
+    fn most_balanced_first() {
+        let data = [
+            entry(S, "Sol Ring", Some(2.0)),
+            entry(V, "Arcane Signet", Some(2.0)),
+            entry(A, "Mana Crypt", Some(150.0)),
+        ];
+        let lists = [
+            (S, "Arcane Signet\nMana Crypt"),
+            (V, "Sol Ring"),
+            (A, "Sol Ring"),
+        ];
+        let trades = trades(&data, &lists, &[]);
+
+        assert_eq!(trades.len(), 2);
+        assert_eq!(members(&trades[0]), [(S, V), (V, S)]);
+        assert_eq!(members(&trades[1]), [(A, S), (S, A)]);
+    }
+
+    #[test]
+    fn prefers_priced_printings() {
+        let data = [
+            entry(S, "Sol Ring", None),
+            entry(S, "Sol Ring", Some(2.0)),
+            entry(V, "Arcane Signet", Some(1.0)),
+        ];
+        let trades = trades(&data, &[(S, "Arcane Signet"), (V, "Sol Ring")], &[]);
+
+        let leg = &trades[0].legs[0];
+        assert_eq!(leg.cards, [1]);
+        assert_eq!(leg.unpriced, 0);
+    }
+
+    #[test]
+    fn unpriced_cards_make_the_balance_unknown() {
+        let data = [
+            entry(S, "Sol Ring", None),
+            entry(V, "Arcane Signet", Some(1.0)),
+        ];
+        let trades = trades(&data, &[(S, "Arcane Signet"), (V, "Sol Ring")], &[]);
+
+        assert!(!trades[0].is_priced());
+        assert_eq!(trades[0].legs[0].value_text(), "0.00€ + ?");
+        assert_eq!(trades[0].balance_text(), "? apart in value");
+    }
+
+    #[test]
+    fn skips_cards_in_decks() {
+        let data = [
+            entry(S, "Sol Ring", Some(2.0)),
+            entry(V, "Arcane Signet", Some(1.0)),
+        ];
+        let deck = Deck {
+            id: 1,
+            name: "Artifacts".into(),
+            owner: S,
+            cards: vec![DeckCard {
+                quantity: 1,
+                name: "Sol Ring".into(),
+                set: String::new(),
+                scryfall: String::new(),
+            }],
+        };
+        let trades = trades(&data, &[(S, "Arcane Signet"), (V, "Sol Ring")], &[deck]);
+
+        assert!(trades.is_empty());
+    }
+}
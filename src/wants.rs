@@ -0,0 +1,182 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::index::{normalize, strip_quantity};
+use archidekt::{Entry, Owner, User};
+
+/// Cards one owner has that a member wants
+#[derive(Debug)]
+pub struct Match {
+    pub owner: Owner,
+    pub wanter: User,
+    /// Indices into the collection
+    pub cards: Vec<usize>,
+}
+
+/// Every member's standing wantlist, kept in `assets/wants/{user}.txt`
+#[derive(Debug, Default)]
+pub struct Wants {
+    lists: Vec<(User, String)>,
+    /// Normalized card names and the members wanting them
+    wanted: HashMap<String, Vec<User>>,
+    /// The members wanting each card in the collection, by its name as it's written there
+    by_card: HashMap<String, Vec<User>>,
+}
+
+impl Wants {
+    /// Each list has one card per line like the wantlist search, lines starting with `#` are
+    /// comments
+    pub fn new(lists: Vec<(User, String)>) -> Self {
+        let mut wanted: HashMap<String, Vec<User>> = HashMap::new();
+        for (user, list) in &lists {
+            let names = list
+                .lines()
+                .map(strip_quantity)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(normalize);
+            for name in names {
+                let wanters = wanted.entry(name).or_default();
+                if !wanters.contains(user) {
+                    wanters.push(*user);
+                }
+            }
+        }
+
+        Self {
+            lists,
+            wanted,
+            by_card: HashMap::new(),
+        }
+    }
+
+    /// Looks up the collection's card names, must be called after the collection changes
+    pub fn index(&mut self, data: &[Entry]) {
+        self.by_card.clear();
+        for entry in data {
+            if !self.by_card.contains_key(&entry.name) {
+                let wanters = self.wanters(&entry.name);
+                if !wanters.is_empty() {
+                    self.by_card.insert(entry.name.clone(), wanters);
+                }
+            }
+        }
+    }
+
+    /// The members with a wantlist and their lists
+    pub fn lists(&self) -> &[(User, String)] {
+        &self.lists
+    }
+
+    /// Members who want the card, double-faced cards may be listed by their front face
+    fn wanters(&self, name: &str) -> Vec<User> {
+        let name = normalize(name);
+        let front = name.split(" // ").next().unwrap_or_default();
+        [name.as_str(), front]
+            .into_iter()
+            .filter_map(|name| self.wanted.get(name))
+            .flatten()
+            .copied()
+            .fold(Vec::new(), |mut wanters, wanter| {
+                if !wanters.contains(&wanter) {
+                    wanters.push(wanter);
+                }
+                wanters
+            })
+    }
+
+    /// Members other than the owner who want the entry's card, see `index`
+    pub fn wanted_by<'a>(&'a self, entry: &'a Entry) -> impl Iterator<Item = User> + 'a {
+        self.by_card
+            .get(&entry.name)
+            .into_iter()
+            .flatten()
+            .copied()
+            .filter(|wanter| entry.owner != *wanter)
+    }
+
+    /// For each owner and member, the cards the owner has that the member wants, see `index`
+    pub fn matches(&self, data: &[Entry]) -> Vec<Match> {
+        let mut matches: BTreeMap<(Owner, User), Vec<usize>> = BTreeMap::new();
+        for (i, entry) in data.iter().enumerate() {
+            for wanter in self.wanted_by(entry) {
+                matches
+                    .entry((entry.owner.clone(), wanter))
+                    .or_default()
+                    .push(i);
+            }
+        }
+
+        matches
+            .into_iter()
+            .map(|((owner, wanter), cards)| Match {
+                owner,
+                wanter,
+                cards,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use archidekt::ColorIdent;
+
+    fn entry(owner: impl Into<Owner>, name: &str) -> Entry {
+        Entry {
+            owner: owner.into(),
+            quantity: 1,
+            color_identity: ColorIdent::new(),
+            name: name.into(),
+            ty: String::new(),
+            set: String::new(),
+            scryfall: String::new(),
+            price: None,
+        }
+    }
+
+    fn wanted_by(wants: &Wants, entry: &Entry) -> Vec<User> {
+        wants.wanted_by(entry).collect()
+    }
+
+    #[test]
+    fn reads_lists_like_the_wantlist_search() {
+        let data = [
+            entry(User::Amon8808, "Sol Ring"),
+            entry(User::Amon8808, "Fire // Ice"),
+            entry(User::Amon8808, "Lim-Dûl's Vault"),
+            entry(User::Amon8808, "Mind Stone"),
+        ];
+        let mut wants = Wants::new(vec![(
+            User::Strosel,
+            "# Artifacts\n2x Sol Ring\n\n   \n1 fire\nlim-dul’s vault\n".into(),
+        )]);
+        wants.index(&data);
+
+        let wanted: Vec<&str> = data
+            .iter()
+            .filter(|entry| !wanted_by(&wants, entry).is_empty())
+            .map(|entry| entry.name.as_str())
+            .collect();
+        assert_eq!(wanted, ["Sol Ring", "Fire // Ice", "Lim-Dûl's Vault"]);
+    }
+
+    #[test]
+    fn owners_dont_want_their_own_cards() {
+        let data = [
+            entry(User::Strosel, "Sol Ring"),
+            entry(User::VikinGG, "Sol Ring"),
+        ];
+        let mut wants = Wants::new(vec![
+            (User::Strosel, "Sol Ring".into()),
+            (User::VikinGG, "sol ring".into()),
+        ]);
+        wants.index(&data);
+
+        assert_eq!(wanted_by(&wants, &data[0]), [User::VikinGG]);
+        assert_eq!(wanted_by(&wants, &data[1]), [User::Strosel]);
+
+        let matches = wants.matches(&data);
+        assert_eq!(matches.len(), 2);
+        assert!(matches.iter().all(|m| m.owner != m.wanter));
+    }
+}
@@ -1,9 +1,29 @@
-use crate::app::App;
-use crate::collection::get_collections;
+use crate::app::{App, Startup};
+use crate::collection::{check_for_update, get_collections, get_history, Freshness};
+use crate::share;
 use anyhow::{anyhow, Result};
+use std::sync::mpsc::channel;
 
 pub async fn start_web(document: &web_sys::Document) -> Result<()> {
-    let data = get_collections().await?;
+    let (loaded, freshness) = get_collections().await?;
+    let history = get_history(freshness != Freshness::Live)
+        .await
+        .unwrap_or_else(|e| {
+            log::warn!("Failed loading price history: {e}");
+            Default::default()
+        });
+
+    // Start from the stored snapshot right away and look for a newer one in the background
+    let updates = match freshness {
+        Freshness::Checking { created } => {
+            let (sender, receiver) = channel();
+            wasm_bindgen_futures::spawn_local(async move {
+                sender.send(check_for_update(created).await).ok();
+            });
+            Some(receiver)
+        }
+        _ => None,
+    };
 
     let canvas = document
         .get_element_by_id("the_canvas_id")
@@ -14,7 +34,15 @@ pub async fn start_web(document: &web_sys::Document) -> Result<()> {
         .start(
             web_sys::HtmlCanvasElement::from(wasm_bindgen::JsValue::from(canvas)),
             web_options,
-            App::creator(data),
+            App::creator(
+                loaded,
+                history,
+                Startup {
+                    freshness,
+                    updates,
+                    search: share::from_fragment(&share::fragment()),
+                },
+            ),
         )
         .await
         .map_err(|e| anyhow!("{:?}", e))?;